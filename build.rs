@@ -55,6 +55,16 @@ pub fn main() {
         let map_value = format!("({krate}::uumain, {krate}::uu_app)");
         phf_map.entry(krate, map_value);
     }
+
+    // The hostname crate also provides a hostnamectl personality, except on Windows.
+    let is_windows = env::var("CARGO_CFG_TARGET_FAMILY")
+        .is_ok_and(|families| families.split(',').any(|family| family == "windows"));
+    if !is_windows && crates.iter().any(|krate| krate == "hostname") {
+        phf_map.entry(
+            "hostnamectl",
+            "(hostname::hostnamectl::uumain, hostname::hostnamectl::uu_app)".to_owned(),
+        );
+    }
     write!(mf, "{}", phf_map.build()).unwrap();
     mf.write_all(b"\n}\n").unwrap();
 
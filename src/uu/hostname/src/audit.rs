@@ -0,0 +1,173 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! An audit trail of host and domain name changes, as JSON lines appended to a file and/or sent
+//! to syslog(3).
+
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+use uucore::show;
+
+use crate::change::Source;
+use crate::utils::json_string;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Change {
+    HostName,
+    DomainName,
+}
+
+pub(crate) struct Audit {
+    log: Option<PathBuf>,
+    syslog: bool,
+    /// Whether failing to write the log was reported, which is done once.
+    log_failed: Cell<bool>,
+}
+
+impl Audit {
+    /// Returns an audit trail writing to `log` and/or to syslog, or `None` if neither is wanted.
+    pub(crate) fn new(log: Option<PathBuf>, syslog: bool) -> Option<Self> {
+        (log.is_some() || syslog).then_some(Self {
+            log,
+            syslog,
+            log_failed: Cell::new(false),
+        })
+    }
+
+    /// Records an attempt to change a name from `old_name` to `new_name`, and its `result`.
+    ///
+    /// Failing to record is reported the first time, but does not undo the change.
+    pub(crate) fn record(
+        &self,
+        change: Change,
+        source: Source,
+        old_name: &CStr,
+        new_name: &CStr,
+        result: &UResult<()>,
+    ) {
+        let (uid, euid) = unsafe { (libc::getuid(), libc::geteuid()) };
+        let optional = |value: Option<String>| value.map_or("null".into(), |v| json_string(&v));
+
+        let line = format!(
+            "{{\"timestamp\":\"{}\",\"change\":\"{}\",\"uid\":{uid},\"euid\":{euid},\
+             \"login\":{},\"parent_cmdline\":{},\"old\":{},\"new\":{},\"source\":\"{}\",\
+             \"result\":{}}}",
+            rfc3339_now(),
+            match change {
+                Change::HostName => "hostname",
+                Change::DomainName => "domainname",
+            },
+            optional(login_name()),
+            optional(parent_command_line()),
+            json_string(&old_name.to_string_lossy()),
+            json_string(&new_name.to_string_lossy()),
+            source.name(),
+            match result {
+                Ok(()) => json_string("success"),
+                Err(err) => json_string(&err.to_string()),
+            },
+        );
+
+        if let Some(path) = &self.log {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(format!("{line}\n").as_bytes()))
+                .map_err_context(|| format!("cannot write audit log {}", path.quote()));
+            if let Err(err) = written
+                && !self.log_failed.replace(true)
+            {
+                show!(err);
+            }
+        }
+
+        if self.syslog {
+            let priority = if result.is_ok() {
+                libc::LOG_NOTICE
+            } else {
+                libc::LOG_WARNING
+            };
+            let message = std::ffi::CString::new(line).unwrap_or_default();
+            unsafe {
+                libc::openlog(c"hostname".as_ptr(), libc::LOG_PID, libc::LOG_AUTHPRIV);
+                libc::syslog(priority, c"%s".as_ptr(), message.as_ptr());
+                libc::closelog();
+            }
+        }
+    }
+}
+
+/// Returns the name of the user logged in on the controlling terminal, which stays the same
+/// through sudo(8) and su(1).
+fn login_name() -> Option<String> {
+    // This program is single-threaded, so the static buffer of getlogin(3) is not shared.
+    let name = unsafe { libc::getlogin() };
+    (!name.is_null()).then(|| {
+        let name = unsafe { CStr::from_ptr(name) };
+        name.to_string_lossy().into_owned()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn parent_command_line() -> Option<String> {
+    let parent = unsafe { libc::getppid() };
+    let command_line = std::fs::read(format!("/proc/{parent}/cmdline")).ok()?;
+    let arguments: Vec<_> = command_line
+        .split(|&b| b == 0)
+        .filter(|argument| !argument.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect();
+    Some(arguments.join(" "))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_command_line() -> Option<String> {
+    None
+}
+
+/// Returns the current time in UTC, such as `2024-05-01T12:34:56Z`.
+fn rfc3339_now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_from_days(days.try_into().unwrap_or(i64::MAX));
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Returns the date of the proleptic Gregorian calendar that is `days` days after 1970-01-01.
+///
+/// Reference: Howard Hinnant, "chrono-Compatible Low-Level Date Algorithms".
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -9,6 +9,89 @@ pub(crate) mod unix;
 pub(crate) mod windows;
 
 #[cfg(not(target_family = "windows"))]
-pub(crate) use unix::{from_argument, from_file};
+pub(crate) use unix::{from_argument, from_file, set_fqdn};
 #[cfg(target_family = "windows")]
 pub(crate) use windows::{from_argument, from_file};
+
+/// The host name set by `-b` when no other one is available.
+pub(crate) const DEFAULT_HOST_NAME: &str = "localhost";
+
+/// Where `--set-fqdn` records the fully qualified name.
+#[cfg(not(target_family = "windows"))]
+#[derive(Clone, Copy)]
+pub(crate) enum FqdnPolicy {
+    /// Map the FQDN and the short name to 127.0.1.1 in `/etc/hosts`.
+    Hosts,
+    /// Store the FQDN as the static host name.
+    HostnameFile,
+    Both,
+}
+
+#[cfg(not(target_family = "windows"))]
+impl FqdnPolicy {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "hostname-file" => Self::HostnameFile,
+            "both" => Self::Both,
+            _ => Self::Hosts,
+        }
+    }
+}
+
+/// Where a new host name comes from, for the audit trail.
+#[cfg(not(target_family = "windows"))]
+#[derive(Clone, Copy, Default)]
+pub(crate) enum Source {
+    #[default]
+    Argument,
+    File,
+    KernelCommandLine,
+    Dhcp,
+    Generated,
+}
+
+#[cfg(not(target_family = "windows"))]
+impl Source {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Argument => "argument",
+            Self::File => "file",
+            Self::KernelCommandLine => "kernel-cmdline",
+            Self::Dhcp => "dhcp",
+            Self::Generated => "generated",
+        }
+    }
+}
+
+/// Options controlling how a new host name is applied.
+#[derive(Default)]
+pub(crate) struct Settings {
+    /// Set [`DEFAULT_HOST_NAME`] if the file given to `-F` is missing or empty.
+    pub(crate) boot: bool,
+    /// Print what the change would do instead of applying it.
+    pub(crate) dry_run: bool,
+    /// Print the new host name instead of applying it.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) preview: bool,
+    /// The system to configure. For an offline image, only its static configuration is written.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) root: crate::sysroot::SysRoot,
+    /// Ask systemd-hostnamed to change the host name, instead of changing it directly.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) hostnamed: Option<crate::dbus::Hostnamed>,
+    /// Turn the new host name into a valid one instead of rejecting it.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) sanitize: bool,
+    /// Hooks to run around the change, unless disabled.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) hooks: Option<crate::hooks::Hooks>,
+    /// Where to record the change, if anywhere.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) audit: Option<crate::audit::Audit>,
+    /// Where the new host name comes from.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) source: Source,
+    /// How to find the host name in the file given to `-F`.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) file_format: crate::file_format::FileFormat,
+}
@@ -4,36 +4,103 @@
 // file that was distributed with this source code.
 
 use std::borrow::Cow;
-use std::ffi::{CString, OsStr};
-use std::path::Path;
+use std::ffi::{CStr, CString, OsStr};
+use std::path::{Path, PathBuf};
 
-use uucore::error::UResult;
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult, set_exit_code};
+use uucore::show;
 
+use crate::audit::Change;
+use crate::change::{DEFAULT_HOST_NAME, FqdnPolicy, Settings};
+use crate::diff;
 use crate::errors::HostNameError;
-use crate::net::set_host_name;
-use crate::utils::parse_host_name_file;
+use crate::file_format::read_host_name;
+use crate::hooks::Stage;
+use crate::hosts;
+use crate::net::{self, host_name_max, set_host_name};
+use crate::sanitize::sanitize;
+use crate::sysroot::{HOSTNAME_FILE, HOSTS_FILE, SysRoot};
+use crate::utils::{verbose, write_file_atomically};
 
-pub(crate) fn from_file(path: &Path) -> UResult<()> {
-    parse_host_name_file(path).map(Cow::Owned).and_then(run)
+pub(crate) fn from_file(path: &Path, settings: &Settings) -> UResult<()> {
+    let host_name = if settings.boot && !path.exists() && path != Path::new("-") {
+        Vec::default()
+    } else {
+        read_host_name(path, &settings.file_format)?
+    };
+
+    if settings.boot && host_name.trim_ascii().is_empty() {
+        run(Cow::Borrowed(DEFAULT_HOST_NAME.as_bytes()), settings)
+    } else {
+        run(Cow::Owned(host_name), settings)
+    }
+}
+
+pub(crate) fn from_argument(host_name: &OsStr, settings: &Settings) -> UResult<()> {
+    run(Cow::Borrowed(os_str_bytes(host_name)), settings)
+}
+
+/// Sets the first label of `fqdn` as the host name, and records `fqdn` itself where `policy`
+/// says, so that the short name, the FQDN and the domain name all agree.
+pub(crate) fn set_fqdn(fqdn: &OsStr, policy: FqdnPolicy, settings: &Settings) -> UResult<()> {
+    let fqdn = prepare(Cow::Borrowed(os_str_bytes(fqdn)), settings)?;
+    let Some(dot) = fqdn.to_bytes().iter().position(|&b| b == b'.') else {
+        return Err(HostNameError::NotFullyQualified.into());
+    };
+    let short_name =
+        CString::new(&fqdn.to_bytes()[..dot]).expect("a valid host name has no null byte");
+
+    let in_hostname_file = matches!(policy, FqdnPolicy::HostnameFile | FqdnPolicy::Both);
+    let in_hosts = matches!(policy, FqdnPolicy::Hosts | FqdnPolicy::Both);
+
+    let static_name = if in_hostname_file { &fqdn } else { &short_name };
+    let mut plan = Plan::new(short_name.clone(), static_name.clone(), settings)?;
+
+    if in_hostname_file && !settings.root.is_image() && settings.hostnamed.is_none() {
+        plan.edit(settings.root.resolve(HOSTNAME_FILE)?, line(&fqdn))?;
+    }
+
+    if in_hosts {
+        let path = settings.root.resolve(HOSTS_FILE)?;
+        let contents = read_file(&path)?.unwrap_or_default();
+        let names = [fqdn.to_bytes(), short_name.to_bytes()];
+        plan.edit(
+            path,
+            hosts::set_names(&contents, hosts::SELF_ADDRESS, &names),
+        )?;
+    }
+
+    plan.carry_out(settings)
 }
 
-pub(crate) fn from_argument(host_name: &OsStr) -> UResult<()> {
+fn os_str_bytes(value: &OsStr) -> &[u8] {
     #[cfg(target_family = "unix")]
-    let host_name = {
+    {
         use std::os::unix::ffi::OsStrExt;
-        Cow::Borrowed(host_name.as_bytes())
-    };
+        value.as_bytes()
+    }
 
     #[cfg(target_family = "wasm")]
-    let host_name = {
+    {
         use std::os::wasm::ffi::OsStrExt;
-        Cow::Borrowed(host_name.as_bytes())
-    };
+        value.as_bytes()
+    }
+}
+
+fn run(host_name: Cow<[u8]>, settings: &Settings) -> UResult<()> {
+    let host_name = prepare(host_name, settings)?;
+
+    if settings.preview {
+        println!("{}", host_name.to_string_lossy());
+        return Ok(());
+    }
 
-    run(host_name)
+    Plan::new(host_name.clone(), host_name, settings)?.carry_out(settings)
 }
 
-fn run(mut host_name: Cow<[u8]>) -> UResult<()> {
+/// Trims white space around a new host name, sanitizes it if requested, and validates it.
+fn prepare(mut host_name: Cow<[u8]>, settings: &Settings) -> UResult<CString> {
     // Trim white space.
     match &mut host_name {
         Cow::Borrowed(name) => *name = name.trim_ascii(),
@@ -49,12 +116,244 @@ fn run(mut host_name: Cow<[u8]>) -> UResult<()> {
         }
     };
 
-    let host_name = validate_host_name(host_name)?;
+    let host_name = if settings.sanitize {
+        let original = String::from_utf8_lossy(&host_name);
+        let (sanitized, changes) = sanitize(&original, host_name_max());
+        if !changes.is_empty() {
+            verbose(
+                1,
+                format_args!(
+                    "sanitized {} to {} ({})",
+                    original.quote(),
+                    sanitized.quote(),
+                    changes.join(", ")
+                ),
+            );
+        }
+        Cow::Owned(sanitized.into_bytes())
+    } else {
+        host_name
+    };
+
+    Ok(validate_host_name(host_name)?)
+}
+
+/// What a change of host name does, worked out before anything is modified.
+struct Plan {
+    /// The host name before the change: the static one of an offline image, the running one
+    /// otherwise.
+    old_name: CString,
+    host_name: CString,
+    /// The static host name, where the change is persisted: in an offline image, or through
+    /// hostnamed.
+    static_name: CString,
+    /// The files to write, with their current contents, or `None` if they do not exist.
+    edits: Vec<(PathBuf, Option<Vec<u8>>, Vec<u8>)>,
+}
+
+impl Plan {
+    fn new(host_name: CString, static_name: CString, settings: &Settings) -> UResult<Self> {
+        let old_name = if settings.root.is_image() {
+            let old_name = settings.root.read_name(HOSTNAME_FILE)?.unwrap_or_default();
+            CString::new(old_name).unwrap_or_default()
+        } else {
+            net::host_name()?
+        };
+
+        let mut plan = Self {
+            old_name,
+            host_name,
+            static_name,
+            edits: Vec::new(),
+        };
+
+        if settings.root.is_image() {
+            let contents = line(&plan.static_name);
+            plan.edit(settings.root.resolve(HOSTNAME_FILE)?, contents)?;
+        }
+        Ok(plan)
+    }
+
+    /// Adds the replacement of the contents of `path` by `contents`, unless they are the same.
+    fn edit(&mut self, path: PathBuf, contents: Vec<u8>) -> UResult<()> {
+        let old_contents = read_file(&path)?;
+        if old_contents.as_ref() != Some(&contents) {
+            self.edits.push((path, old_contents, contents));
+        }
+        Ok(())
+    }
+
+    /// Prints the plan for `--dry-run`, or applies it.
+    fn carry_out(self, settings: &Settings) -> UResult<()> {
+        if settings.dry_run {
+            self.print(settings)
+        } else {
+            self.apply(settings)
+        }
+    }
+
+    /// Prints the new host name, whether it differs from the current one, and a diff of each
+    /// file edit. The exit status is 2 if nothing would change.
+    fn print(&self, settings: &Settings) -> UResult<()> {
+        let mut changed = !self.edits.is_empty();
+        let mut describe = |what: &str, old_name: &str, new_name: &str| {
+            if old_name == new_name {
+                println!("{what}: {} (unchanged)", new_name.quote());
+            } else {
+                println!("{what}: {} -> {}", old_name.quote(), new_name.quote());
+                changed = true;
+            }
+        };
+
+        if settings.root.is_image() {
+            describe(
+                "static host name",
+                &self.old_name.to_string_lossy(),
+                &self.static_name.to_string_lossy(),
+            );
+        } else {
+            describe(
+                "host name",
+                &self.old_name.to_string_lossy(),
+                &self.host_name.to_string_lossy(),
+            );
+            if let Some(hostnamed) = &settings.hostnamed {
+                describe(
+                    "static host name",
+                    &hostnamed.get("StaticHostname")?,
+                    &self.static_name.to_string_lossy(),
+                );
+            }
+        }
+
+        for (path, old_contents, contents) in &self.edits {
+            let label = path.to_string_lossy();
+            print!(
+                "{}",
+                diff::unified(&label, old_contents.as_deref(), contents)
+            );
+        }
+
+        if !changed {
+            set_exit_code(2);
+        }
+        Ok(())
+    }
+
+    /// Makes the file edits and the host name take effect.
+    ///
+    /// The pre-change hooks run first, and any failure of theirs cancels the change. The files
+    /// are written before the host name is set, and restored if either fails, so that the
+    /// running name and the files do not disagree. A failure of the post-change hooks is
+    /// reported, but the change stays.
+    fn apply(self, settings: &Settings) -> UResult<()> {
+        let host_name = &self.host_name;
+        if let Some(hooks) = &settings.hooks {
+            hooks.run(Stage::PreChange, &self.old_name, host_name)?;
+        }
+
+        let result = self.write_edits().and_then(|()| {
+            let result = if settings.root.is_image() {
+                // The host name file is among the edits.
+                Ok(())
+            } else if let Some(hostnamed) = &settings.hostnamed {
+                // Unlike sethostname(2), this also makes the change persistent.
+                hostnamed
+                    .set("SetStaticHostname", &self.static_name.to_string_lossy())
+                    .and_then(|()| hostnamed.set("SetHostname", &host_name.to_string_lossy()))
+            } else {
+                set_host_name(host_name)
+            };
+            if result.is_err() {
+                self.restore_edits(self.edits.len());
+            }
+            result
+        });
+
+        if let Some(audit) = &settings.audit {
+            audit.record(
+                Change::HostName,
+                settings.source,
+                &self.old_name,
+                host_name,
+                &result,
+            );
+        }
+        result?;
+
+        if let Some(hooks) = &settings.hooks
+            && let Err(err) = hooks.run(Stage::PostChange, &self.old_name, host_name)
+        {
+            show!(err);
+        }
+        Ok(())
+    }
+
+    /// Writes the file edits. If one fails, the files already written are restored.
+    fn write_edits(&self) -> UResult<()> {
+        for (written, (path, _, contents)) in self.edits.iter().enumerate() {
+            if let Err(err) = write_file_atomically(path, contents)
+                .map_err_context(|| format!("cannot write {}", path.quote()))
+            {
+                self.restore_edits(written);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts back the previous contents of the first `count` edited files, removing those that
+    /// did not exist. Failures are reported, and do not stop the other files from being
+    /// restored.
+    fn restore_edits(&self, count: usize) {
+        for (path, old_contents, _) in &self.edits[..count] {
+            let result = match old_contents {
+                Some(old_contents) => write_file_atomically(path, old_contents),
+                None => std::fs::remove_file(path),
+            };
+            if let Err(err) = result.map_err_context(|| format!("cannot restore {}", path.quote()))
+            {
+                show!(err);
+            }
+        }
+    }
+}
+
+/// Returns the contents of `path`, or `None` if it does not exist.
+fn read_file(path: &Path) -> UResult<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).map_err_context(|| format!("cannot read {}", path.quote())),
+    }
+}
+
+/// Returns `name` as the line of a file.
+fn line(name: &CStr) -> Vec<u8> {
+    let mut line = name.to_bytes().to_vec();
+    line.push(b'\n');
+    line
+}
+
+/// Stores `host_name` in the host name file of `root`, or removes that file if `host_name` is
+/// `None`.
+pub(crate) fn write_static_host_name(root: &SysRoot, host_name: Option<&CStr>) -> UResult<()> {
+    let path = root.resolve(HOSTNAME_FILE)?;
+
+    let Some(host_name) = host_name else {
+        return match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(err).map_err_context(|| format!("cannot remove {}", path.quote()))
+            }
+            _ => Ok(()),
+        };
+    };
 
-    set_host_name(&host_name)
+    write_file_atomically(&path, &line(host_name))
+        .map_err_context(|| format!("cannot write {}", path.quote()))
 }
 
-fn validate_host_name(host_name: Cow<[u8]>) -> Result<CString, HostNameError> {
+pub(crate) fn validate_host_name(host_name: Cow<[u8]>) -> Result<CString, HostNameError> {
     // Rules:
     // - The only allowed prefix and suffix characters are alphanumeric.
     // - The only allowed characters inside are alphanumeric, '-' and '.'.
@@ -9,21 +9,31 @@ use std::path::Path;
 
 use uucore::error::UResult;
 
+use crate::change::{DEFAULT_HOST_NAME, Settings};
 use crate::errors::HostNameError;
 use crate::net::set_host_name;
 use crate::utils::parse_host_name_file;
 
-pub(crate) fn from_file(path: &Path) -> UResult<()> {
-    let host_name = parse_host_name_file(path)?;
+pub(crate) fn from_file(path: &Path, settings: &Settings) -> UResult<()> {
+    let host_name = if settings.boot && !path.exists() && path != Path::new("-") {
+        Vec::default()
+    } else {
+        parse_host_name_file(path)?
+    };
+
     let host_name = std::str::from_utf8(&host_name).map_err(|_r| HostNameError::InvalidHostName)?;
-    run(host_name.encode_utf16().collect())
+    if settings.boot && host_name.trim_ascii().is_empty() {
+        run(DEFAULT_HOST_NAME.encode_utf16().collect(), settings)
+    } else {
+        run(host_name.encode_utf16().collect(), settings)
+    }
 }
 
-pub(crate) fn from_argument(host_name: &OsStr) -> UResult<()> {
-    run(host_name.encode_wide().collect())
+pub(crate) fn from_argument(host_name: &OsStr, settings: &Settings) -> UResult<()> {
+    run(host_name.encode_wide().collect(), settings)
 }
 
-fn run(mut host_name: Vec<u16>) -> UResult<()> {
+fn run(mut host_name: Vec<u16>, settings: &Settings) -> UResult<()> {
     // Trim white space.
     while host_name.first().is_some_and(u16_is_ascii_whitespace) {
         host_name.remove(0);
@@ -35,6 +45,11 @@ fn run(mut host_name: Vec<u16>) -> UResult<()> {
 
     validate_host_name(&host_name)?;
 
+    if settings.dry_run {
+        println!("{}", String::from_utf16_lossy(&host_name));
+        return Ok(());
+    }
+
     host_name.push(0); // Null-terminate.
     set_host_name(&host_name)
 }
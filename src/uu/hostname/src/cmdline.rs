@@ -0,0 +1,86 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The host name passed on the kernel command line, as `systemd.hostname=` or `hostname=`.
+
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+pub(crate) const PROC_CMDLINE: &str = "/proc/cmdline";
+
+/// Parameters holding the host name, from the most to the least preferred.
+const HOST_NAME_PARAMETERS: [&[u8]; 2] = [b"systemd.hostname", b"hostname"];
+
+/// Returns the host name given on the kernel command line stored in `path`, if any.
+///
+/// When a parameter is repeated, the last value wins.
+pub(crate) fn host_name(path: &Path) -> UResult<Option<OsString>> {
+    let contents =
+        std::fs::read(path).map_err_context(|| format!("cannot read {}", path.quote()))?;
+    let parameters = parameters(&contents);
+
+    let host_name = HOST_NAME_PARAMETERS.iter().find_map(|&wanted| {
+        parameters
+            .iter()
+            .rev()
+            .find_map(|(name, value)| (*name == wanted).then_some(*value).flatten())
+            .filter(|value| !value.is_empty())
+    });
+    Ok(host_name.map(|name| OsString::from_vec(name.to_vec())))
+}
+
+/// Splits a command line into `(name, value)` pairs the way the kernel does, stopping at `--`,
+/// after which the arguments belong to init.
+///
+/// Parameters are separated by white space, except inside double quotes. A parameter wholly in
+/// quotes, or a value starting with a quote, has its surrounding quotes removed.
+///
+/// Reference: `next_arg()` in the Linux kernel's `lib/cmdline.c`.
+fn parameters(cmdline: &[u8]) -> Vec<(&[u8], Option<&[u8]>)> {
+    let mut parameters = Vec::new();
+    let mut rest = cmdline.trim_ascii_start();
+
+    while !rest.is_empty() {
+        let mut in_quote = false;
+        let end = rest
+            .iter()
+            .position(|&c| {
+                if c == b'"' {
+                    in_quote = !in_quote;
+                }
+                c.is_ascii_whitespace() && !in_quote
+            })
+            .unwrap_or(rest.len());
+
+        let (mut parameter, next) = rest.split_at(end);
+        rest = next.trim_ascii_start();
+
+        if let Some(unquoted) = parameter.strip_prefix(b"\"") {
+            parameter = unquoted.strip_suffix(b"\"").unwrap_or(unquoted);
+        }
+
+        if parameter == b"--" {
+            break;
+        }
+
+        parameters.push(match parameter.iter().position(|&c| c == b'=') {
+            Some(equals) => {
+                let (name, value) = (&parameter[..equals], &parameter[equals + 1..]);
+                let value = match value.strip_prefix(b"\"") {
+                    Some(unquoted) => unquoted.strip_suffix(b"\"").unwrap_or(unquoted),
+                    None => value,
+                };
+                (name, Some(value))
+            }
+            None => (parameter, None),
+        });
+    }
+
+    parameters
+}
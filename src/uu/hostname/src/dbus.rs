@@ -0,0 +1,536 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! A minimal D-Bus client, just enough to talk to systemd-hostnamed
+//! (`org.freedesktop.hostname1`) over the system bus.
+//!
+//! Reference: D-Bus Specification, <https://dbus.freedesktop.org/doc/dbus-specification.html>.
+
+// spell-checker:ignore hostnamed
+
+use std::cell::Cell;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use uucore::error::{FromIo, UResult};
+
+use crate::errors::HostNameError;
+
+const SYSTEM_BUS_ADDRESS: &str = "unix:path=/run/dbus/system_bus_socket";
+const CALL_TIMEOUT: Duration = Duration::from_secs(25);
+
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+const ERROR: u8 = 3;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SIGNATURE: u8 = 8;
+
+const HOSTNAMED_NAME: &str = "org.freedesktop.hostname1";
+const HOSTNAMED_PATH: &str = "/org/freedesktop/hostname1";
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("D-Bus: {message}"))
+}
+
+/// Serializes values in the little endian D-Bus wire format.
+#[derive(Default)]
+struct Writer {
+    buffer: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, alignment: usize) {
+        let padded_len = self.buffer.len().next_multiple_of(alignment);
+        self.buffer.resize(padded_len, 0);
+    }
+
+    fn byte(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    fn uint32(&mut self, value: u32) {
+        self.align(4);
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn boolean(&mut self, value: bool) {
+        self.uint32(u32::from(value));
+    }
+
+    fn string(&mut self, value: &str) {
+        self.uint32(u32::try_from(value.len()).unwrap_or(u32::MAX));
+        self.buffer.extend_from_slice(value.as_bytes());
+        self.buffer.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.byte(u8::try_from(value.len()).unwrap_or(u8::MAX));
+        self.buffer.extend_from_slice(value.as_bytes());
+        self.buffer.push(0);
+    }
+
+    fn header_field(&mut self, code: u8, signature: &str, value: &str) {
+        self.align(8);
+        self.byte(code);
+        self.signature(signature);
+        if signature == "g" {
+            self.signature(value);
+        } else {
+            self.string(value);
+        }
+    }
+}
+
+/// Deserializes values in either byte order, with alignment relative to the message start.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn align(&mut self, alignment: usize) {
+        self.position = self.position.next_multiple_of(alignment);
+    }
+
+    fn take(&mut self, len: usize) -> std::io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.saturating_add(len))
+            .ok_or_else(|| invalid_data("truncated message"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> std::io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint32(&mut self) -> std::io::Result<u32> {
+        self.align(4);
+        let bytes: [u8; 4] = self.take(4)?.try_into().expect("4 bytes were taken");
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn boolean(&mut self) -> std::io::Result<bool> {
+        Ok(self.uint32()? != 0)
+    }
+
+    fn string(&mut self) -> std::io::Result<String> {
+        let len = usize::try_from(self.uint32()?).map_err(|_r| invalid_data("string too long"))?;
+        let bytes = self.take(len)?;
+        self.take(1)?; // Terminating null byte.
+        String::from_utf8(bytes.to_vec()).map_err(|_r| invalid_data("invalid UTF-8 string"))
+    }
+
+    fn signature(&mut self) -> std::io::Result<String> {
+        let len = usize::from(self.byte()?);
+        let bytes = self.take(len)?;
+        self.take(1)?; // Terminating null byte.
+        String::from_utf8(bytes.to_vec()).map_err(|_r| invalid_data("invalid signature"))
+    }
+
+    fn string_array(&mut self) -> std::io::Result<Vec<String>> {
+        let len = usize::try_from(self.uint32()?).map_err(|_r| invalid_data("array too long"))?;
+        self.align(4);
+        let end = self.position.saturating_add(len);
+        let mut strings = Vec::new();
+        while self.position < end {
+            strings.push(self.string()?);
+        }
+        Ok(strings)
+    }
+}
+
+/// The body of a method reply.
+struct Reply {
+    signature: String,
+    body: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Reply {
+    fn reader(&self, expected_signature: &str) -> std::io::Result<Reader<'_>> {
+        if self.signature != expected_signature {
+            return Err(invalid_data("unexpected reply signature"));
+        }
+
+        Ok(Reader {
+            data: &self.body,
+            position: 0,
+            big_endian: self.big_endian,
+        })
+    }
+}
+
+/// A connection to a message bus.
+pub(crate) struct Connection {
+    stream: UnixStream,
+    serial: Cell<u32>,
+}
+
+impl Connection {
+    /// Connects to the system bus, whose address can be overridden with
+    /// `DBUS_SYSTEM_BUS_ADDRESS`.
+    pub(crate) fn system() -> std::io::Result<Self> {
+        let address = std::env::var("DBUS_SYSTEM_BUS_ADDRESS")
+            .unwrap_or_else(|_| SYSTEM_BUS_ADDRESS.to_owned());
+
+        let mut last_error = Error::new(ErrorKind::InvalidInput, "D-Bus: no usable bus address");
+        for address in address.split(';') {
+            match Self::open(address) {
+                Ok(connection) => return Ok(connection),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn open(address: &str) -> std::io::Result<Self> {
+        let Some(parameters) = address.strip_prefix("unix:") else {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "D-Bus: unsupported bus address transport",
+            ));
+        };
+
+        let mut stream = None;
+        for parameter in parameters.split(',') {
+            match parameter.split_once('=') {
+                Some(("path", path)) => stream = Some(UnixStream::connect(unescape(path)?)?),
+
+                #[cfg(any(target_os = "android", target_os = "linux"))]
+                Some(("abstract", name)) => {
+                    use std::os::linux::net::SocketAddrExt;
+                    let name = unescape(name)?;
+                    let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                    stream = Some(UnixStream::connect_addr(&address)?);
+                }
+
+                _ => {}
+            }
+        }
+
+        let stream = stream
+            .ok_or_else(|| Error::new(ErrorKind::Unsupported, "D-Bus: unsupported bus address"))?;
+        stream.set_read_timeout(Some(CALL_TIMEOUT))?;
+
+        let connection = Self {
+            stream,
+            serial: Cell::new(0),
+        };
+        connection.authenticate()?;
+        connection
+            .call_bus("Hello", "", Vec::new())
+            .map_err(|err| Error::other(err.to_string()))?;
+        Ok(connection)
+    }
+
+    fn authenticate(&self) -> std::io::Result<()> {
+        let uid = unsafe { libc::geteuid() }.to_string();
+        let hex_uid: String = uid.bytes().map(|b| format!("{b:02x}")).collect();
+
+        let mut stream = &self.stream;
+        stream.write_all(format!("\0AUTH EXTERNAL {hex_uid}\r\n").as_bytes())?;
+
+        let mut line = Vec::new();
+        while !line.ends_with(b"\r\n") {
+            let mut byte = [0_u8; 1];
+            stream.read_exact(&mut byte)?;
+            line.push(byte[0]);
+        }
+
+        if !line.starts_with(b"OK ") {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "D-Bus: authentication rejected",
+            ));
+        }
+
+        stream.write_all(b"BEGIN\r\n")
+    }
+
+    fn call_bus(&self, member: &str, signature: &str, body: Vec<u8>) -> UResult<Reply> {
+        self.call(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            member,
+            signature,
+            body,
+        )
+    }
+
+    /// Calls a method and waits for its reply.
+    fn call(
+        &self,
+        destination: &str,
+        path: &str,
+        interface: &str,
+        member: &str,
+        signature: &str,
+        body: Vec<u8>,
+    ) -> UResult<Reply> {
+        let serial = self.serial.get() + 1;
+        self.serial.set(serial);
+
+        let mut message = Writer::default();
+        message.byte(b'l');
+        message.byte(METHOD_CALL);
+        message.byte(0); // Flags.
+        message.byte(1); // Protocol version.
+        message.uint32(u32::try_from(body.len()).unwrap_or(u32::MAX));
+        message.uint32(serial);
+
+        message.uint32(0); // Length of header fields, patched below.
+        message.align(8);
+        let fields_start = message.buffer.len();
+        message.header_field(FIELD_PATH, "o", path);
+        message.header_field(FIELD_INTERFACE, "s", interface);
+        message.header_field(FIELD_MEMBER, "s", member);
+        message.header_field(FIELD_DESTINATION, "s", destination);
+        if !signature.is_empty() {
+            message.header_field(FIELD_SIGNATURE, "g", signature);
+        }
+        let fields_len = u32::try_from(message.buffer.len() - fields_start).unwrap_or(u32::MAX);
+        message.buffer[12..16].copy_from_slice(&fields_len.to_le_bytes());
+        message.align(8);
+        message.buffer.extend_from_slice(&body);
+
+        (&self.stream).write_all(&message.buffer)?;
+
+        loop {
+            let (message_type, fields, reply) = self.receive()?;
+            if fields.reply_serial != Some(serial) {
+                continue; // Signal, or reply to another call.
+            }
+
+            match message_type {
+                METHOD_RETURN => break Ok(reply),
+
+                ERROR => {
+                    let name = fields.error_name.unwrap_or_default();
+                    let message = reply
+                        .reader("s")
+                        .and_then(|mut r| r.string())
+                        .unwrap_or_default();
+                    break Err(Box::new(HostNameError::DBus(name, message)));
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    fn receive(&self) -> std::io::Result<(u8, HeaderFields, Reply)> {
+        let mut stream = &self.stream;
+
+        let mut header = vec![0_u8; 16];
+        stream.read_exact(&mut header)?;
+
+        let big_endian = match header[0] {
+            b'l' => false,
+            b'B' => true,
+            _ => return Err(invalid_data("invalid byte order")),
+        };
+        let message_type = header[1];
+
+        let mut reader = Reader {
+            data: &header,
+            position: 4,
+            big_endian,
+        };
+        let body_len = reader.uint32()?;
+        let _serial = reader.uint32()?;
+        let fields_len = reader.uint32()?;
+
+        let (Ok(body_len), Ok(fields_len)) =
+            (usize::try_from(body_len), usize::try_from(fields_len))
+        else {
+            return Err(invalid_data("message too long"));
+        };
+
+        let fields_end = 16_usize.saturating_add(fields_len);
+        header.resize(fields_end.next_multiple_of(8), 0);
+        stream.read_exact(&mut header[16..])?;
+
+        let mut body = vec![0_u8; body_len];
+        stream.read_exact(&mut body)?;
+
+        let mut reader = Reader {
+            data: &header[..fields_end],
+            position: 16,
+            big_endian,
+        };
+        let fields = HeaderFields::parse(&mut reader)?;
+        let signature = fields.signature.clone().unwrap_or_default();
+
+        Ok((
+            message_type,
+            fields,
+            Reply {
+                signature,
+                body,
+                big_endian,
+            },
+        ))
+    }
+}
+
+#[derive(Default)]
+struct HeaderFields {
+    error_name: Option<String>,
+    reply_serial: Option<u32>,
+    signature: Option<String>,
+}
+
+impl HeaderFields {
+    fn parse(reader: &mut Reader) -> std::io::Result<Self> {
+        let mut fields = Self::default();
+
+        while reader.position < reader.data.len() {
+            reader.align(8);
+            let code = reader.byte()?;
+            let signature = reader.signature()?;
+
+            match (code, signature.as_str()) {
+                (FIELD_ERROR_NAME, "s") => fields.error_name = Some(reader.string()?),
+                (FIELD_REPLY_SERIAL, "u") => fields.reply_serial = Some(reader.uint32()?),
+                (FIELD_SIGNATURE, "g") => fields.signature = Some(reader.signature()?),
+                (_, "s" | "o") => drop(reader.string()?),
+                (_, "g") => drop(reader.signature()?),
+                (_, "u") => drop(reader.uint32()?),
+                _ => return Err(invalid_data("unsupported header field")),
+            }
+        }
+
+        Ok(fields)
+    }
+}
+
+/// Decodes the `%XX` escapes of a bus address value.
+fn unescape(value: &str) -> std::io::Result<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut chars = value.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex = [chars.next().unwrap_or(0), chars.next().unwrap_or(0)];
+            let hex = std::str::from_utf8(&hex).map_err(|_r| invalid_data("invalid address"))?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_r| invalid_data("invalid address"))?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_r| invalid_data("invalid address"))
+}
+
+/// When to go through systemd-hostnamed instead of changing the system directly.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ViaHostnamed {
+    Never,
+    Auto,
+    Always,
+}
+
+impl ViaHostnamed {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "never" => Self::Never,
+            "auto" => Self::Auto,
+            _ => Self::Always,
+        }
+    }
+}
+
+/// A client of systemd-hostnamed.
+pub(crate) struct Hostnamed(Connection);
+
+impl Hostnamed {
+    /// Connects to hostnamed, or returns `None` if `when` says it must not be used, or if it
+    /// is `Auto` and hostnamed is not available on the system bus.
+    pub(crate) fn connect(when: ViaHostnamed) -> UResult<Option<Self>> {
+        if when == ViaHostnamed::Never {
+            return Ok(None);
+        }
+
+        let connection = match (when, Connection::system()) {
+            (ViaHostnamed::Auto, Err(_)) => return Ok(None),
+            (_, result) => {
+                result.map_err_context(|| "cannot connect to the system bus".to_owned())?
+            }
+        };
+
+        if when == ViaHostnamed::Auto && !Self::is_available(&connection)? {
+            return Ok(None);
+        }
+
+        Ok(Some(Self(connection)))
+    }
+
+    /// Returns `true` if hostnamed is running, or can be started on demand.
+    fn is_available(connection: &Connection) -> UResult<bool> {
+        let mut body = Writer::default();
+        body.string(HOSTNAMED_NAME);
+        let reply = connection.call_bus("NameHasOwner", "s", body.buffer)?;
+        if reply.reader("b")?.boolean()? {
+            return Ok(true);
+        }
+
+        let reply = connection.call_bus("ListActivatableNames", "", Vec::new())?;
+        let names = reply.reader("as")?.string_array()?;
+        Ok(names.iter().any(|name| name == HOSTNAMED_NAME))
+    }
+
+    /// Calls one of the `Set*` methods, which all take a value and an "interactive" flag.
+    pub(crate) fn set(&self, method: &str, value: &str) -> UResult<()> {
+        let mut body = Writer::default();
+        body.string(value);
+        body.boolean(false);
+
+        self.0
+            .call(
+                HOSTNAMED_NAME,
+                HOSTNAMED_PATH,
+                HOSTNAMED_NAME,
+                method,
+                "sb",
+                body.buffer,
+            )
+            .map(|_reply| ())
+    }
+
+    /// Reads a string property, such as `Hostname` or `StaticHostname`.
+    pub(crate) fn get(&self, property: &str) -> UResult<String> {
+        let mut body = Writer::default();
+        body.string(HOSTNAMED_NAME);
+        body.string(property);
+
+        let reply = self.0.call(
+            HOSTNAMED_NAME,
+            HOSTNAMED_PATH,
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            "ss",
+            body.buffer,
+        )?;
+
+        let mut reader = reply.reader("v")?;
+        if reader.signature()? != "s" {
+            return Err(invalid_data("unexpected property type").into());
+        }
+        Ok(reader.string()?)
+    }
+}
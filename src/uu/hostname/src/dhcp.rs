@@ -0,0 +1,336 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The host name assigned by a DHCP server (option 12), as recorded in the lease files of
+//! dhclient and systemd-networkd.
+
+// spell-checker:ignore dhclient netif networkd
+
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+use crate::env_file::EnvFile;
+use crate::errors::HostNameError;
+use crate::sysroot::SysRoot;
+use crate::utils::verbose;
+
+/// Directories holding `dhclient*.leases` files, on Debian and on Red Hat derivatives.
+const DHCLIENT_LEASE_DIRS: [&str; 2] = ["/var/lib/dhcp", "/var/lib/dhclient"];
+/// Directory holding systemd-networkd lease files, named after interface indexes.
+const NETWORKD_LEASE_DIR: &str = "/run/systemd/netif/leases";
+
+/// A lease that assigned a host name.
+struct Lease {
+    interface: Option<String>,
+    host_name: String,
+    /// Expiration time, in seconds since the epoch. `i64::MAX` if the lease never expires.
+    expires: i64,
+}
+
+/// Returns the host name of the newest lease that has not expired: the last one in its lease
+/// file, since dhclient appends renewals, from the lease file written last.
+///
+/// `source` is either an interface name, to only consider the leases of that interface, or the
+/// path of a lease file (anything containing a `/`). Without it, all lease files are searched,
+/// skipping those that cannot be read.
+pub(crate) fn host_name(root: &SysRoot, source: Option<&OsStr>) -> UResult<String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| {
+            duration.as_secs().try_into().unwrap_or(i64::MAX)
+        });
+    // The newest current lease of each file, and when the file was written.
+    let mut candidates = Vec::new();
+    let mut add = |path: &Path, leases: Vec<Lease>, interface: Option<&String>| {
+        let newest = leases.into_iter().rfind(|lease| {
+            lease.expires > now
+                && interface.is_none_or(|name| lease.interface.as_ref() == Some(name))
+        });
+        if let Some(lease) = newest {
+            let written = std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(UNIX_EPOCH);
+            candidates.push((written, lease));
+        }
+    };
+
+    match source {
+        Some(path) if path.as_bytes().contains(&b'/') => {
+            let path = Path::new(path);
+            let contents = read(path)?;
+            if String::from_utf8_lossy(&contents).contains("lease {") {
+                add(path, parse_dhclient_leases(&contents), None);
+            } else {
+                let lease = parse_networkd_lease(path, &contents, None)?;
+                add(path, lease.into_iter().collect(), None);
+            }
+        }
+
+        interface => {
+            let interface = interface.map(|name| name.to_string_lossy().into_owned());
+
+            for dir in DHCLIENT_LEASE_DIRS {
+                for path in list_dir(&root.resolve(dir)?)? {
+                    let is_dhclient_lease = path.file_name().is_some_and(|name| {
+                        let name = name.to_string_lossy();
+                        name.starts_with("dhclient") && name.ends_with(".leases")
+                    });
+                    if is_dhclient_lease && let Some(contents) = read_or_skip(&path) {
+                        add(&path, parse_dhclient_leases(&contents), interface.as_ref());
+                    }
+                }
+            }
+
+            let index = interface.as_deref().map(interface_index).transpose()?;
+            for path in list_dir(&root.resolve(NETWORKD_LEASE_DIR)?)? {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                if index.is_none_or(|index| file_name == index.to_string())
+                    && let Some(contents) = read_or_skip(&path)
+                {
+                    let lease = parse_networkd_lease(&path, &contents, interface.clone())?;
+                    add(&path, lease.into_iter().collect(), interface.as_ref());
+                }
+            }
+        }
+    }
+
+    // On ties, the lease that was read last wins.
+    candidates
+        .into_iter()
+        .max_by_key(|(written, _)| *written)
+        .map(|(_, lease)| lease.host_name)
+        .ok_or_else(|| HostNameError::NoDhcpHostName.into())
+}
+
+/// Reads the lease file `path`, or reports why it cannot be read with `--verbose` and returns
+/// `None`, so that one unreadable file does not stop the search.
+fn read_or_skip(path: &Path) -> Option<Vec<u8>> {
+    match read(path) {
+        Ok(contents) => Some(contents),
+        Err(err) => {
+            verbose(1, format_args!("skipping lease file: {err}"));
+            None
+        }
+    }
+}
+
+fn read(path: &Path) -> UResult<Vec<u8>> {
+    std::fs::read(path).map_err_context(|| format!("cannot read {}", path.quote()))
+}
+
+/// Returns the paths of the entries of `dir`, sorted, or nothing if `dir` does not exist.
+fn list_dir(dir: &Path) -> UResult<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).map_err_context(|| format!("cannot read {}", dir.quote())),
+    };
+
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err_context(|| format!("cannot read {}", dir.quote()))?;
+    paths.sort();
+    Ok(paths)
+}
+
+fn interface_index(name: &str) -> UResult<u32> {
+    let c_name = CString::new(name).map_err(|_r| HostNameError::NoSuchInterface(name.into()))?;
+    match unsafe { libc::if_nametoindex(c_name.as_ptr()) } {
+        0 => Err(HostNameError::NoSuchInterface(name.into()).into()),
+        index => Ok(index),
+    }
+}
+
+/// Parses a systemd-networkd lease file, whose `HOSTNAME` is valid for `LIFETIME` seconds after
+/// the file was written.
+fn parse_networkd_lease(
+    path: &Path,
+    contents: &[u8],
+    interface: Option<String>,
+) -> UResult<Option<Lease>> {
+    let file = EnvFile::parse(&String::from_utf8_lossy(contents)).map_err(|err| {
+        HostNameError::Syntax {
+            path: path.to_owned(),
+            line: err.line,
+            message: err.message,
+        }
+    })?;
+
+    let Some(host_name) = file.get("HOSTNAME").filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+
+    let expires = match file
+        .get("LIFETIME")
+        .and_then(|value| value.parse::<i64>().ok())
+    {
+        Some(lifetime) => {
+            let written = std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .map_err_context(|| format!("cannot read {}", path.quote()))?;
+            let written = written.duration_since(UNIX_EPOCH).map_or(0, |duration| {
+                duration.as_secs().try_into().unwrap_or(i64::MAX)
+            });
+            written.saturating_add(lifetime)
+        }
+        None => i64::MAX,
+    };
+
+    Ok(Some(Lease {
+        interface,
+        host_name: host_name.to_owned(),
+        expires,
+    }))
+}
+
+/// Parses the `lease { ... }` blocks of a dhclient lease file, keeping those with a host name.
+///
+/// Reference: dhclient.leases(5).
+fn parse_dhclient_leases(contents: &[u8]) -> Vec<Lease> {
+    let contents = String::from_utf8_lossy(contents);
+    let mut tokens = tokenize(&contents).into_iter().peekable();
+    let mut leases = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        if token != Token::Word("lease") || tokens.peek() != Some(&Token::Open) {
+            continue;
+        }
+        tokens.next();
+
+        let mut interface = None;
+        let mut host_name = None;
+        let mut expires = i64::MAX;
+        let mut statement = Vec::new();
+
+        for token in tokens.by_ref() {
+            match token {
+                Token::Close => break,
+                Token::End => {
+                    match statement.as_slice() {
+                        [Token::Word("interface"), Token::String(name)] => {
+                            interface = Some(name.clone());
+                        }
+                        [
+                            Token::Word("option"),
+                            Token::Word("host-name"),
+                            Token::String(name),
+                        ] => {
+                            host_name = Some(name.clone());
+                        }
+                        [Token::Word("expire"), time @ ..] => {
+                            expires = parse_dhclient_time(time).unwrap_or(0);
+                        }
+                        _ => {}
+                    }
+                    statement.clear();
+                }
+                token => statement.push(token),
+            }
+        }
+
+        if let Some(host_name) = host_name.filter(|name| !name.is_empty()) {
+            leases.push(Lease {
+                interface,
+                host_name,
+                expires,
+            });
+        }
+    }
+
+    leases
+}
+
+/// Parses the time of an `expire` statement: `never`, `epoch SECONDS`, or the default
+/// `WEEKDAY YYYY/MM/DD HH:MM:SS` in UTC.
+fn parse_dhclient_time(tokens: &[Token]) -> Option<i64> {
+    match tokens {
+        [Token::Word("never")] => Some(i64::MAX),
+        [Token::Word("epoch"), Token::Word(seconds)] => seconds.parse().ok(),
+        [Token::Word(_weekday), Token::Word(date), Token::Word(time)] => {
+            let mut date = date.split('/').map(str::parse::<i64>);
+            let mut time = time.split(':').map(str::parse::<i64>);
+            let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+            let (hour, minute, second) =
+                (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+            Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the number of days between 1970-01-01 and the given date of the proleptic Gregorian
+/// calendar.
+///
+/// Reference: Howard Hinnant, "chrono-Compatible Low-Level Date Algorithms".
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    String(String),
+    Open,
+    Close,
+    End,
+}
+
+/// Splits a dhclient lease file into words, quoted strings and punctuation, dropping comments.
+fn tokenize(contents: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = contents;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' | '}' | ';' => {
+                tokens.push(match c {
+                    '{' => Token::Open,
+                    '}' => Token::Close,
+                    _ => Token::End,
+                });
+                rest = &rest[1..];
+            }
+
+            '#' => rest = rest.split_once('\n').map_or("", |(_, rest)| rest),
+
+            '"' => {
+                let mut value = String::new();
+                let mut chars = rest[1..].char_indices();
+                rest = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                        Some((index, '"')) => break &rest[1 + index + 1..],
+                        Some((_, c)) => value.push(c),
+                        None => break "",
+                    }
+                };
+                tokens.push(Token::String(value));
+            }
+
+            c if c.is_whitespace() => rest = rest.trim_start(),
+
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{};\"#".contains(c))
+                    .unwrap_or(rest.len());
+                tokens.push(Token::Word(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+    }
+
+    tokens
+}
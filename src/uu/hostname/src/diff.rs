@@ -0,0 +1,144 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Unified diffs of the small system files edited along with the host name.
+
+use std::fmt::Write;
+
+/// Lines of unchanged text shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// A step of the edit script, along with the numbers of old and new lines before it.
+#[derive(Clone, Copy)]
+struct Step {
+    op: Op,
+    old_index: usize,
+    new_index: usize,
+}
+
+/// Returns the unified diff from `old` to `new` contents of the file `label`, in the format of
+/// `diff -u`. `old` is `None` if the file does not exist yet.
+pub(crate) fn unified(label: &str, old: Option<&[u8]>, new: &[u8]) -> String {
+    let old_lines: Vec<_> = old
+        .unwrap_or_default()
+        .split_inclusive(|&b| b == b'\n')
+        .collect();
+    let new_lines: Vec<_> = new.split_inclusive(|&b| b == b'\n').collect();
+    let steps = edit_script(&old_lines, &new_lines);
+
+    let mut diff = format!(
+        "--- {}\n+++ {label}\n",
+        if old.is_some() { label } else { "/dev/null" }
+    );
+
+    let mut next = 0;
+    while let Some(start) = steps[next..]
+        .iter()
+        .position(|step| step.op != Op::Equal)
+        .map(|offset| next + offset)
+    {
+        // Changes separated by little enough unchanged text share a hunk.
+        let mut end = start;
+        loop {
+            end += steps[end..]
+                .iter()
+                .take_while(|step| step.op != Op::Equal)
+                .count();
+            let unchanged = steps[end..]
+                .iter()
+                .take_while(|step| step.op == Op::Equal)
+                .count();
+            if end + unchanged == steps.len() || unchanged > 2 * CONTEXT {
+                break;
+            }
+            end += unchanged;
+        }
+
+        let hunk = &steps[start.saturating_sub(CONTEXT)..(end + CONTEXT).min(steps.len())];
+        let old_count = hunk.iter().filter(|step| step.op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|step| step.op != Op::Delete).count();
+        // An empty range is numbered after the line that precedes it.
+        let old_start = hunk[0].old_index + usize::from(old_count > 0);
+        let new_start = hunk[0].new_index + usize::from(new_count > 0);
+        let _ = writeln!(
+            diff,
+            "@@ -{old_start},{old_count} +{new_start},{new_count} @@"
+        );
+
+        for step in hunk {
+            let (prefix, line) = match step.op {
+                Op::Equal => (' ', old_lines[step.old_index]),
+                Op::Delete => ('-', old_lines[step.old_index]),
+                Op::Insert => ('+', new_lines[step.new_index]),
+            };
+            let text = line.strip_suffix(b"\n");
+            let _ = writeln!(
+                diff,
+                "{prefix}{}",
+                String::from_utf8_lossy(text.unwrap_or(line))
+            );
+            if text.is_none() {
+                diff.push_str("\\ No newline at end of file\n");
+            }
+        }
+
+        next = (end + CONTEXT).min(steps.len());
+    }
+
+    diff
+}
+
+/// Returns a shortest edit script turning `old` into `new`, from their longest common
+/// subsequence. The files are small, so the quadratic table is not a concern.
+fn edit_script(old: &[&[u8]], new: &[&[u8]]) -> Vec<Step> {
+    let width = new.len() + 1;
+    // common[i * width + j] is the length of the longest common subsequence of old[i..] and
+    // new[j..].
+    let mut common = vec![0_usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i] == new[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut steps = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let op = if i < old.len() && j < new.len() && old[i] == new[j] {
+            Op::Equal
+        } else if j == new.len()
+            || i < old.len() && common[(i + 1) * width + j] >= common[i * width + j + 1]
+        {
+            Op::Delete
+        } else {
+            Op::Insert
+        };
+
+        steps.push(Step {
+            op,
+            old_index: i,
+            new_index: j,
+        });
+        if op != Op::Insert {
+            i += 1;
+        }
+        if op != Op::Delete {
+            j += 1;
+        }
+    }
+
+    steps
+}
@@ -0,0 +1,410 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--doctor`: gathers every notion of the host name the system has, and reports where they
+//! disagree.
+
+use std::ffi::CStr;
+use std::net::IpAddr;
+
+use libc::{AF_UNSPEC, AI_CANONNAME, NI_NAMEREQD, NI_NUMERICHOST, SOCK_DGRAM};
+use uucore::display::Quotable;
+use uucore::error::{UResult, set_exit_code};
+
+use crate::escape::Escape;
+use crate::hosts;
+use crate::machine_info::{METADATA_KEYS, MachineInfo};
+use crate::net::{
+    AddressInfo, InterfaceAddresses, filter_map_interface_addresses, get_name_info, host_name,
+};
+use crate::sysroot::{HOSTNAME_FILE, HOSTS_FILE, SysRoot};
+use crate::utils::json_string;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+struct Finding {
+    severity: Severity,
+    message: String,
+    suggestion: String,
+}
+
+/// What the system says its name is, from each source.
+#[derive(Default)]
+struct Facts {
+    kernel_name: String,
+    static_name: Option<String>,
+    /// `$HOSTNAME`, as set by the shell that started this program.
+    environment_name: Option<String>,
+    canonical_name: Option<String>,
+    /// The addresses the kernel name resolves to.
+    addresses: Vec<String>,
+    /// Why the kernel name does not resolve.
+    resolve_error: Option<String>,
+    /// The addresses `/etc/hosts` maps the kernel name to.
+    hosts_addresses: Vec<String>,
+    /// The first address of an interface that is up and not a loopback one, and its interface.
+    primary_address: Option<(String, String)>,
+    /// Every address of the interfaces that are up and not loopback ones.
+    interface_addresses: Vec<String>,
+    /// The name the primary address resolves back to, or why it does not.
+    reverse_name: Option<Result<String, String>>,
+    /// The keys of `/etc/machine-info` that describe the machine, and their values if set.
+    machine_info: Vec<(&'static str, Option<String>)>,
+}
+
+/// Prints the sources of the host name and what disagrees, as text with the names escaped by
+/// `escape`, or as JSON.
+pub(crate) fn run(format: &str, escape: &Escape) -> UResult<()> {
+    let facts = gather()?;
+    let findings = diagnose(&facts);
+
+    if format == "json" {
+        print_json(&facts, &findings);
+    } else {
+        print_text(&facts, &findings, escape);
+        escape.warn();
+    }
+
+    if findings
+        .iter()
+        .any(|finding| finding.severity == Severity::Error)
+    {
+        set_exit_code(1);
+    }
+    Ok(())
+}
+
+fn gather() -> UResult<Facts> {
+    let lossy = |name: &CStr| name.to_string_lossy().into_owned();
+    let root = SysRoot::default();
+
+    let kernel_name = host_name()?;
+    let mut facts = Facts {
+        kernel_name: lossy(&kernel_name),
+        static_name: root
+            .read_name(HOSTNAME_FILE)?
+            .map(|name| String::from_utf8_lossy(&name).into_owned()),
+        environment_name: std::env::var_os("HOSTNAME")
+            .map(|name| name.to_string_lossy().into_owned()),
+        machine_info: {
+            let machine_info = MachineInfo::read_or_default();
+            METADATA_KEYS
+                .into_iter()
+                .map(|key| (key, machine_info.get(key).map(str::to_owned)))
+                .collect()
+        },
+        ..Facts::default()
+    };
+
+    match AddressInfo::new(&kernel_name, AF_UNSPEC, SOCK_DGRAM, 0, AI_CANONNAME) {
+        Ok(address_info) => {
+            facts.canonical_name = address_info.canonical_name().map(lossy);
+
+            for ai in address_info.iter() {
+                let address = lossy(&get_name_info(ai.ai_addr, ai.ai_addrlen, NI_NUMERICHOST)?);
+                if !facts.addresses.contains(&address) {
+                    facts.addresses.push(address);
+                }
+            }
+        }
+        Err(err) => facts.resolve_error = Some(err.to_string()),
+    }
+
+    if let Ok(contents) = std::fs::read(root.resolve(HOSTS_FILE)?) {
+        facts.hosts_addresses = hosts::entries(&contents)
+            .filter(|entry| entry.contains(kernel_name.to_bytes()))
+            .map(|entry| String::from_utf8_lossy(entry.address).into_owned())
+            .collect();
+    }
+
+    let interface_addresses = InterfaceAddresses::new()?;
+    for ifa in interface_addresses.iter() {
+        let Some((addr, size)) = filter_map_interface_addresses(ifa) else {
+            continue;
+        };
+        let address = lossy(&get_name_info(addr.as_ptr(), size, NI_NUMERICHOST)?);
+
+        if facts.primary_address.is_none() {
+            let interface = lossy(unsafe { CStr::from_ptr(ifa.ifa_name) });
+            facts.primary_address = Some((address.clone(), interface));
+            facts.reverse_name = Some(
+                get_name_info(addr.as_ptr(), size, NI_NAMEREQD)
+                    .map(|name| lossy(&name))
+                    .map_err(|err| err.to_string()),
+            );
+        }
+        facts.interface_addresses.push(address);
+    }
+
+    Ok(facts)
+}
+
+fn diagnose(facts: &Facts) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut report = |severity, message: String, suggestion: String| {
+        findings.push(Finding {
+            severity,
+            message,
+            suggestion,
+        });
+    };
+    let kernel_name = &facts.kernel_name;
+
+    match &facts.static_name {
+        None => report(
+            Severity::Warning,
+            format!("{HOSTNAME_FILE} does not hold a host name"),
+            format!("write {} to {HOSTNAME_FILE}", kernel_name.quote()),
+        ),
+        Some(static_name) if static_name != kernel_name => report(
+            Severity::Warning,
+            format!(
+                "kernel name {} differs from {HOSTNAME_FILE} ({})",
+                kernel_name.quote(),
+                static_name.quote()
+            ),
+            format!("run 'hostname -F {HOSTNAME_FILE}', or update {HOSTNAME_FILE}"),
+        ),
+        Some(_) => {}
+    }
+
+    if let Some(environment_name) = &facts.environment_name
+        && environment_name != kernel_name
+    {
+        report(
+            Severity::Info,
+            format!(
+                "$HOSTNAME ({}) differs from the kernel name {}",
+                environment_name.quote(),
+                kernel_name.quote()
+            ),
+            "start a new login shell to pick up the current name".into(),
+        );
+    }
+
+    match &facts.resolve_error {
+        Some(err) => report(
+            Severity::Error,
+            format!(
+                "kernel name {} does not resolve: {err}",
+                kernel_name.quote()
+            ),
+            format!(
+                "add a line such as {} to {HOSTS_FILE}, or a DNS record",
+                format!(
+                    "{} FQDN {kernel_name}",
+                    String::from_utf8_lossy(hosts::SELF_ADDRESS)
+                )
+                .quote()
+            ),
+        ),
+
+        None => {
+            let addresses = &facts.addresses;
+            let name = facts.canonical_name.as_ref().unwrap_or(kernel_name);
+            if !name.contains('.') {
+                report(
+                    Severity::Warning,
+                    format!("canonical name {} is not fully qualified", name.quote()),
+                    format!(
+                        "list the FQDN before {} on its line of {HOSTS_FILE}",
+                        kernel_name.quote()
+                    ),
+                );
+            }
+
+            if !addresses.is_empty() && addresses.iter().all(|address| is_loopback(address)) {
+                report(
+                    Severity::Warning,
+                    format!("{} resolves to {} only", name.quote(), addresses.join(", ")),
+                    "map the name to a routable address if other hosts must reach it by name"
+                        .into(),
+                );
+            }
+        }
+    }
+
+    for address in &facts.hosts_addresses {
+        if !is_loopback(address) && !facts.interface_addresses.contains(address) {
+            report(
+                Severity::Error,
+                format!(
+                    "{HOSTS_FILE} maps {} to {address}, which is not an address of this host",
+                    kernel_name.quote()
+                ),
+                format!("correct the address in {HOSTS_FILE}"),
+            );
+        }
+    }
+
+    match (&facts.primary_address, &facts.reverse_name) {
+        (Some((address, _)), Some(Err(err))) => report(
+            Severity::Warning,
+            format!("primary address {address} has no reverse DNS name: {err}"),
+            format!("add a PTR record for {address}"),
+        ),
+        (Some((address, _)), Some(Ok(reverse_name))) => {
+            let expected = facts.canonical_name.as_ref().unwrap_or(kernel_name);
+            if !reverse_name.eq_ignore_ascii_case(expected) {
+                report(
+                    Severity::Warning,
+                    format!(
+                        "reverse DNS of primary address {address} is {}, not {}",
+                        reverse_name.quote(),
+                        expected.quote()
+                    ),
+                    format!("point the PTR record of {address} to {}", expected.quote()),
+                );
+            }
+        }
+        _ => report(
+            Severity::Info,
+            "no interface is up besides the loopback one".into(),
+            "bring up a network interface to check reverse DNS".into(),
+        ),
+    }
+
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    findings
+}
+
+/// Returns `true` if `address` is in `127.0.0.0/8` or is `::1`.
+fn is_loopback(address: &str) -> bool {
+    address
+        .parse::<IpAddr>()
+        .is_ok_and(|address| address.is_loopback())
+}
+
+fn print_text(facts: &Facts, findings: &[Finding], escape: &Escape) {
+    let none = || "(none)".to_owned();
+    let rows = [
+        ("kernel host name", facts.kernel_name.clone()),
+        (
+            "static host name",
+            facts.static_name.clone().unwrap_or_else(none),
+        ),
+        (
+            "$HOSTNAME",
+            facts.environment_name.clone().unwrap_or_else(none),
+        ),
+        (
+            "canonical name",
+            facts.canonical_name.clone().unwrap_or_else(none),
+        ),
+        (
+            "resolved addresses",
+            match &facts.resolve_error {
+                Some(err) => format!("(error: {err})"),
+                None => facts.addresses.join(" "),
+            },
+        ),
+        ("addresses in /etc/hosts", facts.hosts_addresses.join(" ")),
+        (
+            "primary address",
+            facts
+                .primary_address
+                .as_ref()
+                .map_or_else(none, |(address, interface)| {
+                    format!("{address} ({interface})")
+                }),
+        ),
+        (
+            "reverse DNS name",
+            match &facts.reverse_name {
+                Some(Ok(name)) => name.clone(),
+                Some(Err(err)) => format!("(error: {err})"),
+                None => none(),
+            },
+        ),
+    ];
+
+    for (label, value) in rows {
+        println!(
+            "{:<25}{}",
+            format!("{label}:"),
+            escape.name(value.as_bytes())
+        );
+    }
+
+    println!();
+    if findings.is_empty() {
+        println!("no problems found");
+    }
+    for finding in findings {
+        println!("{}: {}", finding.severity.name(), finding.message);
+        println!("  fix: {}", finding.suggestion);
+    }
+}
+
+fn print_json(facts: &Facts, findings: &[Finding]) {
+    let optional = |value: Option<&String>| value.map_or("null".into(), |v| json_string(v));
+    let list = |values: &[String]| {
+        let values: Vec<_> = values.iter().map(|value| json_string(value)).collect();
+        format!("[{}]", values.join(","))
+    };
+    let (reverse_name, reverse_error) = match &facts.reverse_name {
+        Some(Ok(name)) => (Some(name), None),
+        Some(Err(err)) => (None, Some(err)),
+        None => (None, None),
+    };
+
+    let machine_info: Vec<_> = facts
+        .machine_info
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), optional(value.as_ref())))
+        .collect();
+    let findings: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            format!(
+                "{{\"severity\":\"{}\",\"message\":{},\"suggestion\":{}}}",
+                finding.severity.name(),
+                json_string(&finding.message),
+                json_string(&finding.suggestion)
+            )
+        })
+        .collect();
+
+    println!(
+        "{{\"kernel_host_name\":{},\"static_host_name\":{},\"environment_host_name\":{},\
+         \"canonical_name\":{},\"addresses\":{},\"resolve_error\":{},\
+         \"hosts_addresses\":{},\"primary_address\":{},\"primary_interface\":{},\
+         \"reverse_name\":{},\"reverse_error\":{},\"machine_info\":{{{}}},\
+         \"findings\":[{}]}}",
+        json_string(&facts.kernel_name),
+        optional(facts.static_name.as_ref()),
+        optional(facts.environment_name.as_ref()),
+        optional(facts.canonical_name.as_ref()),
+        list(&facts.addresses),
+        optional(facts.resolve_error.as_ref()),
+        list(&facts.hosts_addresses),
+        optional(facts.primary_address.as_ref().map(|(address, _)| address)),
+        optional(
+            facts
+                .primary_address
+                .as_ref()
+                .map(|(_, interface)| interface)
+        ),
+        optional(reverse_name),
+        optional(reverse_error),
+        machine_info.join(","),
+        findings.join(",")
+    );
+}
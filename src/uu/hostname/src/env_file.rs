@@ -0,0 +1,247 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Reading and editing of environment-like files of `KEY=VALUE` assignments, such as
+//! `/etc/machine-info`.
+//!
+//! Values follow shell quoting rules. Edits only touch the lines of the assigned keys: comments,
+//! blank lines, unknown keys and the quoting of other values are kept as they are.
+
+use std::fmt;
+
+/// An error in an environment file, at a 1-based line number.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ParseError {
+    pub(crate) line: usize,
+    pub(crate) message: &'static str,
+}
+
+enum Line {
+    Assignment {
+        raw: String,
+        key: String,
+        value: String,
+    },
+    Other(String),
+}
+
+#[derive(Default)]
+pub(crate) struct EnvFile {
+    lines: Vec<Line>,
+}
+
+impl EnvFile {
+    pub(crate) fn parse(contents: &str) -> Result<Self, ParseError> {
+        let mut lines = Vec::new();
+        let mut physical_lines = contents.lines().enumerate();
+
+        while let Some((index, line)) = physical_lines.next() {
+            let mut raw = line.to_owned();
+
+            let assignment = loop {
+                match parse_line(&raw) {
+                    Err(Incomplete) => {
+                        let Some((_, next)) = physical_lines.next() else {
+                            return Err(ParseError {
+                                line: index + 1,
+                                message: "unterminated quoted string or line continuation",
+                            });
+                        };
+                        raw.push('\n');
+                        raw.push_str(next);
+                    }
+                    Ok(assignment) => break assignment,
+                }
+            };
+
+            lines.push(match assignment {
+                Some((key, value)) => Line::Assignment { raw, key, value },
+                None => Line::Other(raw),
+            });
+        }
+
+        Ok(Self { lines })
+    }
+
+    /// Returns the value assigned to `key`. As in a shell, the last assignment wins.
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+            .last()
+    }
+
+    /// Returns all assignments, in file order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Assignment { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Other(_) => None,
+        })
+    }
+
+    /// Assigns `value` to `key`, replacing the existing assignment in place, or appending a new
+    /// one. An empty `value` removes the key altogether.
+    pub(crate) fn set(&mut self, key: &str, value: &str) {
+        let is_key = |line: &Line| matches!(line, Line::Assignment { key: k, .. } if k == key);
+
+        let Some(last) = self.lines.iter().rposition(is_key) else {
+            if !value.is_empty() {
+                self.lines.push(Line::Assignment {
+                    raw: format!("{key}={}", quote(value)),
+                    key: key.to_owned(),
+                    value: value.to_owned(),
+                });
+            }
+            return;
+        };
+
+        if value.is_empty() {
+            self.lines.retain(|line| !is_key(line));
+            return;
+        }
+
+        if let Line::Assignment { raw, value: v, .. } = &mut self.lines[last] {
+            let prefix = if raw.trim_start().starts_with("export ") {
+                "export "
+            } else {
+                ""
+            };
+            *raw = format!("{prefix}{key}={}", quote(value));
+            value.clone_into(v);
+        }
+
+        // Drop earlier assignments, which were overridden anyway.
+        let mut index = 0;
+        self.lines.retain(|line| {
+            index += 1;
+            index - 1 == last || !is_key(line)
+        });
+    }
+}
+
+impl fmt::Display for EnvFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Assignment { raw, .. } | Line::Other(raw) => writeln!(f, "{raw}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if `key` is usable as a variable name.
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    let mut bytes = key.bytes();
+    bytes
+        .next()
+        .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Quotes `value` for the right-hand side of an assignment, if it needs it.
+pub(crate) fn quote(value: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        return value.to_owned();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The logical line continues on the next physical line.
+struct Incomplete;
+
+/// Parses a logical line, returning the key and unquoted value of an assignment, or `None` for
+/// blank lines, comments and anything else that is not an assignment.
+fn parse_line(line: &str) -> Result<Option<(String, String)>, Incomplete> {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with(['#', ';']) {
+        return Ok(None);
+    }
+
+    let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+    let Some((key, raw_value)) = line.split_once('=') else {
+        return Ok(None);
+    };
+    if !is_valid_key(key) {
+        return Ok(None);
+    }
+
+    unquote(raw_value).map(|value| Some((key.to_owned(), value)))
+}
+
+/// Unquotes the right-hand side of an assignment.
+///
+/// Single quotes preserve everything literally. Within double quotes, a backslash only escapes
+/// `"`, `\`, `$`, `` ` `` and newlines. Outside of quotes, a backslash escapes any character,
+/// and white space followed by `#` starts a comment. Trailing white space is ignored.
+fn unquote(raw: &str) -> Result<String, Incomplete> {
+    let mut value = String::with_capacity(raw.len());
+    // Length of `value` up to the last quoted or escaped character, which must be kept even
+    // when trimming trailing white space.
+    let mut kept_len = 0;
+    let mut after_space = raw.starts_with(char::is_whitespace);
+    let mut chars = raw.trim_start().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                loop {
+                    match chars.next().ok_or(Incomplete)? {
+                        '\'' => break,
+                        c => value.push(c),
+                    }
+                }
+                kept_len = value.len();
+            }
+
+            '"' => {
+                loop {
+                    match chars.next().ok_or(Incomplete)? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or(Incomplete)? {
+                            '\n' => {}
+                            c @ ('"' | '\\' | '$' | '`') => value.push(c),
+                            c => {
+                                value.push('\\');
+                                value.push(c);
+                            }
+                        },
+                        c => value.push(c),
+                    }
+                }
+                kept_len = value.len();
+            }
+
+            '\\' => match chars.next().ok_or(Incomplete)? {
+                '\n' => {}
+                c => {
+                    value.push(c);
+                    kept_len = value.len();
+                }
+            },
+
+            '#' if after_space => break,
+
+            c => value.push(c),
+        }
+
+        after_space = c.is_whitespace();
+    }
+
+    let trimmed_len = value.trim_end().len().max(kept_len);
+    value.truncate(trimmed_len);
+    Ok(value)
+}
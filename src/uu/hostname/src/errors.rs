@@ -5,14 +5,48 @@
 
 use std::fmt;
 
+#[cfg(not(target_family = "windows"))]
+use uucore::display::Quotable;
 use uucore::error::UError;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum HostNameError {
     InvalidHostName,
+    #[cfg(not(target_family = "windows"))]
+    DBus(String, String),
+    #[cfg(not(target_family = "windows"))]
+    InvalidFormat(String),
+    #[cfg(not(target_family = "windows"))]
+    InvalidMachineInfo(String),
+    #[cfg(not(target_family = "windows"))]
+    InvalidTemplate(String),
+    #[cfg(not(target_family = "windows"))]
+    Syntax {
+        path: std::path::PathBuf,
+        line: usize,
+        message: &'static str,
+    },
+    #[cfg(not(target_family = "windows"))]
+    EscapesRoot(std::path::PathBuf),
+    #[cfg(not(target_family = "windows"))]
+    MissingKey(std::path::PathBuf, String),
+    #[cfg(not(target_family = "windows"))]
+    NoSuchInterface(String),
     HostNameTooLong,
     NoLocalDomainName,
+    #[cfg(target_os = "linux")]
+    NoCmdlineHostName,
+    #[cfg(not(target_family = "windows"))]
+    NoDhcpHostName,
+    #[cfg(not(target_family = "windows"))]
+    NoTemplateValue(String),
+    #[cfg(not(target_family = "windows"))]
+    NoStaticHostName,
+    #[cfg(not(target_family = "windows"))]
+    NotFullyQualified,
     SetHostNameDenied,
+    #[cfg(target_os = "linux")]
+    SetDomainNameDenied,
     #[cfg(not(target_family = "windows"))]
     GetNameOrAddrInfo(GetNameOrAddrInfoError),
 }
@@ -21,9 +55,45 @@ impl fmt::Display for HostNameError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InvalidHostName => write!(f, "the specified hostname is invalid"),
+            #[cfg(not(target_family = "windows"))]
+            Self::DBus(name, message) => write!(f, "{message} ({name})"),
+            #[cfg(not(target_family = "windows"))]
+            Self::InvalidFormat(reason) => write!(f, "invalid format: {reason}"),
+            #[cfg(not(target_family = "windows"))]
+            Self::InvalidMachineInfo(key) => write!(f, "invalid value for {key}"),
+            #[cfg(not(target_family = "windows"))]
+            Self::InvalidTemplate(reason) => write!(f, "invalid template: {reason}"),
+            #[cfg(not(target_family = "windows"))]
+            Self::Syntax {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.maybe_quote()),
+            #[cfg(not(target_family = "windows"))]
+            Self::EscapesRoot(path) => write!(
+                f,
+                "refusing to follow {}: it leads outside of the root directory",
+                path.quote()
+            ),
+            #[cfg(not(target_family = "windows"))]
+            Self::MissingKey(path, key) => write!(f, "{}: {key} is not set", path.maybe_quote()),
+            #[cfg(not(target_family = "windows"))]
+            Self::NoSuchInterface(name) => write!(f, "no such network interface: {name}"),
             Self::HostNameTooLong => write!(f, "name too long"),
             Self::NoLocalDomainName => write!(f, "local domain name not set"),
+            #[cfg(target_os = "linux")]
+            Self::NoCmdlineHostName => write!(f, "no host name on the kernel command line"),
+            #[cfg(not(target_family = "windows"))]
+            Self::NoDhcpHostName => write!(f, "no current DHCP lease with a host name"),
+            #[cfg(not(target_family = "windows"))]
+            Self::NoTemplateValue(name) => write!(f, "no value available for {{{name}}}"),
+            #[cfg(not(target_family = "windows"))]
+            Self::NoStaticHostName => write!(f, "static host name not set"),
+            #[cfg(not(target_family = "windows"))]
+            Self::NotFullyQualified => write!(f, "the specified name is not fully qualified"),
             Self::SetHostNameDenied => write!(f, "you must be root to change the host name"),
+            #[cfg(target_os = "linux")]
+            Self::SetDomainNameDenied => write!(f, "you must be root to change the domain name"),
             #[cfg(not(target_family = "windows"))]
             Self::GetNameOrAddrInfo(r) => write!(f, "{r}"),
         }
@@ -43,7 +113,7 @@ impl UError for HostNameError {
 impl std::error::Error for HostNameError {}
 
 #[cfg(not(target_family = "windows"))]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct GetNameOrAddrInfoError(pub(crate) std::ffi::c_int);
 
 #[cfg(not(target_family = "windows"))]
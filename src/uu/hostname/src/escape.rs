@@ -0,0 +1,159 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--escape`: keeps names from the kernel, an image or DNS from writing control sequences to
+//! the terminal.
+//!
+//! A name may hold any byte but NUL, so a container or a PTR record can make it hold ANSI
+//! escape sequences.
+
+use std::cell::Cell;
+use std::io::IsTerminal;
+
+use uucore::show_warning;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// Print names as they are.
+    Never,
+    /// Print control and non-UTF-8 bytes as `\xNN`, and `\` as `\\`.
+    C,
+    /// Quote each word that a POSIX shell would not read back as it is.
+    Shell,
+}
+
+pub(crate) struct Escape {
+    style: Style,
+    /// Warn when something was escaped, since the user did not ask for it.
+    auto: bool,
+    escaped: Cell<bool>,
+}
+
+impl Escape {
+    /// Returns the escaping of `mode`: `never`, `c`, `shell`, or `auto`, which is `c` when
+    /// standard output is a terminal and `never` otherwise.
+    pub(crate) fn new(mode: &str) -> Self {
+        let style = match mode {
+            "never" => Style::Never,
+            "c" => Style::C,
+            "shell" => Style::Shell,
+            _ if std::io::stdout().is_terminal() => Style::C,
+            _ => Style::Never,
+        };
+
+        Self {
+            style,
+            auto: mode == "auto",
+            escaped: Cell::new(false),
+        }
+    }
+
+    /// Returns `true` if names are printed as they are.
+    pub(crate) fn is_never(&self) -> bool {
+        self.style == Style::Never
+    }
+
+    /// Appends `value` to `out`, escaped. In the shell style, the words of `value`, which are
+    /// separated by spaces as in the output of `-I`, are quoted one by one.
+    pub(crate) fn push(&self, out: &mut Vec<u8>, value: &[u8]) {
+        match self.style {
+            Style::Never => out.extend_from_slice(value),
+
+            Style::C => {
+                let start = out.len();
+                for chunk in value.utf8_chunks() {
+                    for c in chunk.valid().chars() {
+                        let mut buffer = [0; 4];
+                        let bytes = c.encode_utf8(&mut buffer).as_bytes();
+                        if c == '\\' {
+                            out.extend_from_slice(b"\\\\");
+                        } else if c.is_control() {
+                            bytes.iter().for_each(|&b| push_hex(out, b));
+                        } else {
+                            out.extend_from_slice(bytes);
+                        }
+                    }
+                    chunk.invalid().iter().for_each(|&b| push_hex(out, b));
+                }
+                if out[start..] != *value {
+                    self.escaped.set(true);
+                }
+            }
+
+            Style::Shell => {
+                for (i, word) in value.split(|&b| b == b' ').enumerate() {
+                    if i > 0 {
+                        out.push(b' ');
+                    }
+                    self.push_shell_word(out, word);
+                }
+            }
+        }
+    }
+
+    fn push_shell_word(&self, out: &mut Vec<u8>, word: &[u8]) {
+        let is_plain = |b: u8| b.is_ascii_alphanumeric() || b"%+,-./:=@_".contains(&b);
+        if word.iter().all(|&b| is_plain(b)) {
+            out.extend_from_slice(word);
+            return;
+        }
+        self.escaped.set(true);
+
+        let printable =
+            std::str::from_utf8(word).is_ok_and(|word| !word.chars().any(char::is_control));
+        if printable {
+            out.push(b'\'');
+            for &b in word {
+                if b == b'\'' {
+                    out.extend_from_slice(b"'\\''");
+                } else {
+                    out.push(b);
+                }
+            }
+            out.push(b'\'');
+            return;
+        }
+
+        // ANSI-C quoting, which bash, zsh, ksh and the 2024 edition of POSIX sh understand.
+        out.extend_from_slice(b"$'");
+        for chunk in word.utf8_chunks() {
+            for c in chunk.valid().chars() {
+                let mut buffer = [0; 4];
+                let bytes = c.encode_utf8(&mut buffer).as_bytes();
+                if c == '\\' || c == '\'' {
+                    out.push(b'\\');
+                    out.extend_from_slice(bytes);
+                } else if c.is_control() {
+                    bytes.iter().for_each(|&b| push_hex(out, b));
+                } else {
+                    out.extend_from_slice(bytes);
+                }
+            }
+            chunk.invalid().iter().for_each(|&b| push_hex(out, b));
+        }
+        out.push(b'\'');
+    }
+
+    /// Returns `name` escaped, as text for a report.
+    pub(crate) fn name(&self, name: &[u8]) -> String {
+        let mut escaped = Vec::new();
+        self.push(&mut escaped, name);
+        String::from_utf8_lossy(&escaped).into_owned()
+    }
+
+    /// Warns that the output was escaped without being asked to, if it was.
+    pub(crate) fn warn(&self) {
+        if self.auto && self.escaped.get() {
+            show_warning!(
+                "names hold control or non-UTF-8 bytes, printed as \\xNN escapes; use \
+                 --escape=never to print them as they are"
+            );
+        }
+    }
+}
+
+fn push_hex(out: &mut Vec<u8>, b: u8) {
+    out.extend_from_slice(format!("\\x{b:02x}").as_bytes());
+}
@@ -0,0 +1,103 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore setgroups
+
+use std::ffi::{CString, OsStr, OsString};
+use std::io::{Error, ErrorKind};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult, USimpleError};
+
+use crate::audit::{Audit, Change};
+use crate::change;
+use crate::errors::HostNameError;
+use crate::net::{self, set_domain_name};
+
+/// Runs `command` in a new UTS namespace whose host name is `host_name` and, optionally, whose
+/// NIS domain name is `domain_name`.
+///
+/// On success, this does not return: the current process image is replaced by `command`.
+pub(crate) fn run(
+    host_name: &OsStr,
+    domain_name: Option<&OsStr>,
+    command: &[OsString],
+    audit: Option<Audit>,
+) -> UResult<()> {
+    let (program, arguments) = command.split_first().expect("command must be specified");
+
+    unshare_uts_namespace()?;
+
+    let settings = change::Settings {
+        audit,
+        ..change::Settings::default()
+    };
+    change::from_argument(host_name, &settings)?;
+
+    if let Some(new_name) = domain_name {
+        let new_name =
+            CString::new(new_name.as_bytes()).map_err(|_r| HostNameError::InvalidHostName)?;
+        let old_name = match &settings.audit {
+            Some(_) => net::domain_name()?.unwrap_or_default(),
+            None => CString::default(),
+        };
+
+        let result = set_domain_name(&new_name);
+        if let Some(audit) = &settings.audit {
+            audit.record(
+                Change::DomainName,
+                settings.source,
+                &old_name,
+                &new_name,
+                &result,
+            );
+        }
+        result?;
+    }
+
+    let err = std::process::Command::new(program).args(arguments).exec();
+    let code = if err.kind() == ErrorKind::NotFound {
+        127
+    } else {
+        126
+    };
+    Err(USimpleError::new(
+        code,
+        format!("failed to run command {}: {err}", program.quote()),
+    ))
+}
+
+fn unshare_uts_namespace() -> UResult<()> {
+    if unsafe { libc::unshare(libc::CLONE_NEWUTS) } == 0 {
+        return Ok(());
+    }
+
+    let err = Error::last_os_error();
+    if err.kind() != ErrorKind::PermissionDenied || unsafe { libc::geteuid() } == 0 {
+        return Err(err).map_err_context(|| "cannot create UTS namespace".into());
+    }
+
+    // Unprivileged: a new user namespace grants the capabilities needed to own a UTS namespace.
+    // Map the caller's identity onto itself, so files keep their usual ownership.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+
+    if unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWUTS) } == -1 {
+        return Err(Error::last_os_error())
+            .map_err_context(|| "cannot create user and UTS namespaces".into());
+    }
+
+    // Writing gid_map is only permitted once setgroups(2) is disabled.
+    [
+        ("/proc/self/setgroups", "deny".to_owned()),
+        ("/proc/self/uid_map", format!("{uid} {uid} 1")),
+        ("/proc/self/gid_map", format!("{gid} {gid} 1")),
+    ]
+    .into_iter()
+    .try_for_each(|(path, contents)| {
+        std::fs::write(path, contents).map_err_context(|| format!("cannot write {}", path.quote()))
+    })
+}
@@ -0,0 +1,119 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--export`: the names of the host as shell variable assignments, for
+//! `eval "$(hostname --export)"`.
+//!
+//! Host names come from the kernel and the resolver, and may hold any byte but NUL, so every
+//! value is quoted, whatever it looks like.
+
+use std::io::Write;
+use std::rc::Rc;
+
+use uucore::error::UResult;
+use uucore::show;
+
+use crate::format::{evaluate, printer};
+use crate::machine_info::{METADATA_KEYS, MachineInfo};
+use crate::print::HostLookup;
+
+/// The variables, and the `--format` placeholders of their values.
+const VARIABLES: [(&str, char); 6] = [
+    ("HOSTNAME", 'h'),
+    ("HOSTNAME_SHORT", 's'),
+    ("FQDN", 'f'),
+    ("DNSDOMAIN", 'd'),
+    ("NISDOMAIN", 'y'),
+    ("IP_ADDRESSES", 'I'),
+];
+
+/// Prints an assignment of every variable, then of each key of `/etc/machine-info` that
+/// describes the machine, in the syntax of `shell`: `sh`, `fish` or `pwsh`.
+///
+/// A value that cannot be found is reported and set to the empty string, so that the output
+/// can still be evaluated.
+pub(crate) fn run(shell: &str, fallback: bool) -> UResult<()> {
+    let lookup = Rc::new(HostLookup::default());
+    let machine_info = MachineInfo::read_or_default();
+    let mut output = Vec::new();
+
+    let names = VARIABLES.into_iter().map(|(variable, placeholder)| {
+        let value = match evaluate(&*printer(placeholder, &lookup, fallback)) {
+            Ok(value) => value,
+            Err(err) => {
+                show!(err);
+                Vec::new()
+            }
+        };
+        (variable, value)
+    });
+    let metadata = METADATA_KEYS.into_iter().map(|key| {
+        let value = machine_info.get(key).unwrap_or_default();
+        (key, value.as_bytes().to_vec())
+    });
+
+    for (variable, value) in names.chain(metadata) {
+        match shell {
+            "fish" => {
+                write!(output, "set -gx {variable} ")?;
+                fish_quote(&mut output, &value);
+            }
+            "pwsh" => {
+                write!(output, "$env:{variable} = ")?;
+                pwsh_quote(&mut output, &value);
+            }
+            _ => {
+                write!(output, "export {variable}=")?;
+                sh_quote(&mut output, &value);
+            }
+        }
+        output.push(b'\n');
+    }
+
+    std::io::stdout().write_all(&output).map_err(From::from)
+}
+
+/// Single-quotes `value` for a POSIX shell, in which nothing but `'` is special between single
+/// quotes. Each `'` closes the quotes, is escaped, and opens them again.
+fn sh_quote(output: &mut Vec<u8>, value: &[u8]) {
+    output.push(b'\'');
+    for &b in value {
+        if b == b'\'' {
+            output.extend_from_slice(b"'\\''");
+        } else {
+            output.push(b);
+        }
+    }
+    output.push(b'\'');
+}
+
+/// Single-quotes `value` for fish, in which `\` and `'` are escaped with a backslash between
+/// single quotes.
+fn fish_quote(output: &mut Vec<u8>, value: &[u8]) {
+    output.push(b'\'');
+    for &b in value {
+        if b == b'\'' || b == b'\\' {
+            output.push(b'\\');
+        }
+        output.push(b);
+    }
+    output.push(b'\'');
+}
+
+/// Single-quotes `value` for PowerShell, in which quotes are escaped by doubling them between
+/// single quotes. PowerShell also takes the typographic single quotes U+2018 to U+201B for `'`.
+///
+/// PowerShell reads its input as text, so bytes that are not UTF-8 become U+FFFD.
+fn pwsh_quote(output: &mut Vec<u8>, value: &[u8]) {
+    let mut buffer = [0; 4];
+    output.push(b'\'');
+    for c in String::from_utf8_lossy(value).chars() {
+        if matches!(c, '\'' | '\u{2018}'..='\u{201B}') {
+            output.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+        output.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+    }
+    output.push(b'\'');
+}
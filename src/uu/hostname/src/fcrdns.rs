@@ -0,0 +1,110 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--check-fcrdns`: forward-confirmed reverse DNS of the addresses of this host.
+//!
+//! An address passes when its PTR name resolves back to it. Mail servers and Kerberos rely on
+//! this to trust the name a host presents.
+
+use libc::{AF_UNSPEC, NI_NAMEREQD, NI_NUMERICHOST, SOCK_DGRAM};
+use uucore::error::{UResult, set_exit_code};
+
+use crate::errors::{GetNameOrAddrInfoError, HostNameError};
+use crate::escape::Escape;
+use crate::net::{AddressInfo, InterfaceAddresses, filter_map_interface_addresses, get_name_info};
+
+struct Check {
+    address: String,
+    /// The PTR name of the address, if it has one.
+    name: Option<String>,
+    /// `None` if the check passed, or why it failed.
+    failure: Option<String>,
+}
+
+/// Checks every address that `-I` prints, and prints a table of the results. The exit status
+/// is 1 if any address fails. PTR names are escaped by `escape`.
+pub(crate) fn run(escape: &Escape) -> UResult<()> {
+    const NONAME: HostNameError =
+        HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(libc::EAI_NONAME));
+
+    let interface_addresses = InterfaceAddresses::new()?;
+    let mut checks = Vec::new();
+
+    for (addr, size) in interface_addresses
+        .iter()
+        .filter_map(filter_map_interface_addresses)
+    {
+        let address = match get_name_info(addr.as_ptr(), size, NI_NUMERICHOST) {
+            Ok(address) => address.to_string_lossy().into_owned(),
+            Err(err) if err == NONAME => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        let check = match get_name_info(addr.as_ptr(), size, NI_NAMEREQD) {
+            Err(err) => Check {
+                address,
+                name: None,
+                failure: Some(format!("no PTR record: {err}")),
+            },
+            Ok(name) => {
+                let failure = match AddressInfo::new(&name, AF_UNSPEC, SOCK_DGRAM, 0, 0) {
+                    Err(err) => Some(format!("PTR name does not resolve: {err}")),
+                    Ok(address_info) => {
+                        let confirmed = address_info.iter().any(|ai| {
+                            get_name_info(ai.ai_addr, ai.ai_addrlen, NI_NUMERICHOST)
+                                .is_ok_and(|forward| *forward.to_string_lossy() == address)
+                        });
+                        (!confirmed).then(|| "PTR name resolves to other addresses".to_owned())
+                    }
+                };
+                Check {
+                    address,
+                    name: Some(escape.name(name.to_bytes())),
+                    failure,
+                }
+            }
+        };
+        checks.push(check);
+    }
+
+    print_table(&checks);
+    escape.warn();
+
+    if checks.iter().any(|check| check.failure.is_some()) {
+        set_exit_code(1);
+    }
+    Ok(())
+}
+
+fn print_table(checks: &[Check]) {
+    let address_width = checks
+        .iter()
+        .map(|check| check.address.len())
+        .chain(["ADDRESS".len()])
+        .max()
+        .unwrap_or_default();
+    let name_width = checks
+        .iter()
+        .map(|check| check.name.as_ref().map_or(1, String::len))
+        .chain(["PTR NAME".len()])
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{:address_width$}  {:name_width$}  RESULT",
+        "ADDRESS", "PTR NAME"
+    );
+    for check in checks {
+        let result = match &check.failure {
+            None => "pass".to_owned(),
+            Some(reason) => format!("fail ({reason})"),
+        };
+        println!(
+            "{:address_width$}  {:name_width$}  {result}",
+            check.address,
+            check.name.as_deref().unwrap_or("-"),
+        );
+    }
+}
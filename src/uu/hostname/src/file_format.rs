@@ -0,0 +1,187 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Formats of the files that `-F` reads the host name from.
+
+use std::io::Read;
+use std::path::Path;
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+use crate::env_file::{EnvFile, is_valid_key};
+use crate::errors::HostNameError;
+use crate::utils::parse_host_name;
+
+/// Keys tried, in order, when the format is detected.
+const AUTO_SHELL_VARS: [&str; 2] = ["HOSTNAME", "hostname"];
+const AUTO_YAML_KEYS: [&str; 2] = ["local-hostname", "hostname"];
+
+#[derive(Clone, Default)]
+pub(crate) enum FileFormat {
+    /// The first line that is neither empty nor a comment, as in `/etc/hostname`.
+    #[default]
+    Plain,
+    /// A shell variable assignment, as in `/etc/sysconfig/network` or `/etc/rc.conf`.
+    ShellVar(String),
+    /// A top-level key of a YAML mapping, as in a cloud-init `meta-data` file.
+    YamlKey(String),
+    /// One of the above, guessed from the contents.
+    Auto,
+}
+
+impl FileFormat {
+    /// Parses the value of `--file-format`.
+    pub(crate) fn from_name(name: &str) -> Result<Self, String> {
+        match name.split_once(':') {
+            None if name == "plain" => Ok(Self::Plain),
+            None if name == "auto" => Ok(Self::Auto),
+            Some(("shell-var", key)) if is_valid_key(key) => Ok(Self::ShellVar(key.to_owned())),
+            Some(("yaml-key", key)) if !key.is_empty() => Ok(Self::YamlKey(key.to_owned())),
+            _ => Err("expected plain, shell-var:NAME, yaml-key:NAME or auto".to_owned()),
+        }
+    }
+}
+
+/// Reads the host name from `path`, or from the standard input if `path` is `-`.
+pub(crate) fn read_host_name(path: &Path, format: &FileFormat) -> UResult<Vec<u8>> {
+    let mut contents = Vec::new();
+    if path == Path::new("-") {
+        std::io::stdin().lock().read_to_end(&mut contents)
+    } else {
+        std::fs::File::open(path).and_then(|mut file| file.read_to_end(&mut contents))
+    }
+    .map_err_context(|| format!("cannot read {}", path.quote()))?;
+
+    match format {
+        FileFormat::Plain => parse_host_name(contents.as_slice()),
+
+        FileFormat::ShellVar(key) => {
+            let file = parse_env_file(&contents, path)?;
+            lookup(file.get(key), key, path)
+        }
+
+        FileFormat::YamlKey(key) => {
+            let value = yaml_value(&contents, key).map_err(|line| syntax(path, line))?;
+            lookup(value.as_deref(), key, path)
+        }
+
+        FileFormat::Auto => {
+            for key in AUTO_YAML_KEYS {
+                if let Ok(Some(value)) = yaml_value(&contents, key) {
+                    return Ok(value.into_bytes());
+                }
+            }
+
+            if let Ok(file) = parse_env_file(&contents, path) {
+                for key in AUTO_SHELL_VARS {
+                    if let Some(value) = file.get(key) {
+                        return Ok(value.as_bytes().to_vec());
+                    }
+                }
+            }
+
+            parse_host_name(contents.as_slice())
+        }
+    }
+}
+
+fn parse_env_file(contents: &[u8], path: &Path) -> Result<EnvFile, HostNameError> {
+    EnvFile::parse(&String::from_utf8_lossy(contents)).map_err(|err| HostNameError::Syntax {
+        path: path.to_owned(),
+        line: err.line,
+        message: err.message,
+    })
+}
+
+fn lookup(value: Option<&str>, key: &str, path: &Path) -> UResult<Vec<u8>> {
+    match value {
+        Some(value) => Ok(value.as_bytes().to_vec()),
+        None => Err(HostNameError::MissingKey(path.to_owned(), key.to_owned()).into()),
+    }
+}
+
+fn syntax(path: &Path, line: usize) -> HostNameError {
+    HostNameError::Syntax {
+        path: path.to_owned(),
+        line,
+        message: "unterminated quoted string",
+    }
+}
+
+/// Returns the value of the top-level `key` of a YAML mapping, or `None` if it is absent.
+///
+/// Only block mappings with scalar values are understood, which is what cloud-init seed files
+/// contain in practice. Plain, single-quoted and double-quoted scalars are supported. On error,
+/// returns the 1-based number of the offending line.
+fn yaml_value(contents: &[u8], key: &str) -> Result<Option<String>, usize> {
+    let contents = String::from_utf8_lossy(contents);
+    let mut value = None;
+
+    for (index, line) in contents.lines().enumerate() {
+        // Indented lines belong to nested values, and "---" starts a document.
+        if line.starts_with([' ', '\t', '#', '-']) {
+            continue;
+        }
+
+        let Some((line_key, raw_value)) = line.split_once(':') else {
+            continue;
+        };
+        if unquote_yaml(line_key.trim_end()).ok().as_deref() != Some(key) {
+            continue;
+        }
+        if !raw_value.is_empty() && !raw_value.starts_with([' ', '\t']) {
+            continue; // Not a mapping key, e.g. "key:value".
+        }
+
+        value = Some(unquote_yaml(raw_value.trim()).map_err(|()| index + 1)?);
+    }
+
+    Ok(value)
+}
+
+/// Unquotes a YAML scalar on a single line.
+fn unquote_yaml(raw: &str) -> Result<String, ()> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let mut value = String::with_capacity(rest.len());
+        let mut chars = rest.chars();
+        loop {
+            match chars.next().ok_or(())? {
+                '\'' if chars.as_str().starts_with('\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => return Ok(value),
+                c => value.push(c),
+            }
+        }
+    }
+
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::with_capacity(rest.len());
+        let mut chars = rest.chars();
+        loop {
+            match chars.next().ok_or(())? {
+                '\\' => match chars.next().ok_or(())? {
+                    't' => value.push('\t'),
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                '"' => return Ok(value),
+                c => value.push(c),
+            }
+        }
+    }
+
+    // A plain scalar ends at a comment.
+    if raw.starts_with('#') {
+        return Ok(String::new());
+    }
+    let value = match raw.find(" #") {
+        Some(end) => &raw[..end],
+        None => raw,
+    };
+    Ok(value.trim_end().to_owned())
+}
@@ -0,0 +1,147 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--format`: several names laid out by a template such as `%s.%d\n`.
+//!
+//! Placeholders are `%h` (the kernel host name), `%s` (short), `%f` (FQDN), `%d` (DNS domain),
+//! `%y` (NIS domain), `%i` (resolved addresses) and `%I` (interface addresses), and `%%` stands
+//! for a literal `%`. Escapes are `\n`, `\t`, `\0` and `\\`.
+
+use std::io::Write;
+use std::rc::Rc;
+
+use uucore::error::UResult;
+use uucore::show;
+
+use crate::errors::HostNameError;
+use crate::escape::Escape;
+use crate::print::{
+    AllIpAddressesHostName, DefaultHostName, DomainHostName, FqdnHostName, HostLookup,
+    IpAddressHostName, NisHostName, PrintHostName, ShortHostName,
+};
+
+enum Piece {
+    Text(Vec<u8>),
+    Placeholder(char),
+}
+
+/// Prints `template` with its placeholders replaced, their values escaped by `escape`. A
+/// placeholder is only evaluated when the template contains it, so that, for instance, a
+/// template without `%f`, `%d` or `%i` never queries the resolver.
+///
+/// A placeholder whose value cannot be obtained is reported and left empty, so that the others
+/// are still printed; the exit status is then 1.
+pub(crate) fn run(template: &str, fallback: bool, escape: &Escape) -> UResult<()> {
+    let pieces = parse(template)?;
+    let lookup = Rc::new(HostLookup::default());
+    let mut output = Vec::new();
+
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => output.extend_from_slice(&text),
+            Piece::Placeholder(placeholder) => {
+                match evaluate(&*printer(placeholder, &lookup, fallback)) {
+                    Ok(value) => escape.push(&mut output, &value),
+                    Err(err) => show!(err),
+                }
+            }
+        }
+    }
+
+    std::io::stdout().write_all(&output)?;
+    escape.warn();
+    Ok(())
+}
+
+/// Returns the printer of the value that `placeholder` stands for, one of `h`, `s`, `f`, `d`,
+/// `y`, `i` and `I`.
+pub(crate) fn printer(
+    placeholder: char,
+    lookup: &Rc<HostLookup>,
+    fallback: bool,
+) -> Box<dyn PrintHostName> {
+    match placeholder {
+        'h' => Box::new(DefaultHostName { hostnamed: None }),
+        's' => Box::new(ShortHostName),
+        'f' => Box::new(FqdnHostName {
+            lookup: lookup.clone(),
+            fallback,
+        }),
+        'd' => Box::new(DomainHostName {
+            lookup: lookup.clone(),
+            fallback,
+        }),
+        'y' => Box::new(NisHostName),
+        'i' => Box::new(IpAddressHostName {
+            lookup: lookup.clone(),
+            fallback,
+        }),
+        _ => Box::new(AllIpAddressesHostName {
+            #[cfg(target_os = "linux")]
+            netns: None,
+        }),
+    }
+}
+
+/// Returns what `printer` prints, without the final newline.
+pub(crate) fn evaluate(printer: &dyn PrintHostName) -> UResult<Vec<u8>> {
+    let mut value = Vec::new();
+    printer.print_host_name(&mut value)?;
+    if value.last() == Some(&b'\n') {
+        value.pop();
+    }
+    Ok(value)
+}
+
+/// Splits `template` into text and placeholders, so that a template error is reported before
+/// anything is looked up.
+fn parse(template: &str) -> Result<Vec<Piece>, HostNameError> {
+    let mut pieces = Vec::new();
+    let mut text = Vec::new();
+    let mut chars = template.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => match chars.next() {
+                Some('%') => text.push(b'%'),
+                Some(placeholder @ ('h' | 's' | 'f' | 'd' | 'y' | 'i' | 'I')) => {
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Placeholder(placeholder));
+                }
+                Some(other) => {
+                    return Err(HostNameError::InvalidFormat(format!(
+                        "unknown placeholder %{other}"
+                    )));
+                }
+                None => return Err(HostNameError::InvalidFormat("trailing %".into())),
+            },
+
+            '\\' => match chars.next() {
+                Some('n') => text.push(b'\n'),
+                Some('t') => text.push(b'\t'),
+                Some('0') => text.push(0),
+                Some('\\') => text.push(b'\\'),
+                Some(other) => {
+                    return Err(HostNameError::InvalidFormat(format!(
+                        "unknown escape \\{other}"
+                    )));
+                }
+                None => return Err(HostNameError::InvalidFormat("trailing \\".into())),
+            },
+
+            c => {
+                let mut buffer = [0; 4];
+                text.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
@@ -0,0 +1,159 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Host names generated from a template such as `web-{mac:6}`.
+//!
+//! Placeholders are replaced by a value of the machine: `{mac}` (the hardware address of the
+//! first interface that is not a loopback), `{machine-id}`, `{serial}` (the DMI product serial
+//! number) and `{random:hex|dec|alnum}`. An optional `:N` keeps `N` characters: the last ones of
+//! a MAC address or serial number, which vary the most, and the first ones otherwise. `{{` and
+//! `}}` stand for literal braces.
+
+// spell-checker:ignore alnum
+
+use std::io::Read;
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+use crate::errors::HostNameError;
+use crate::sanitize::sanitize_label;
+use crate::sysroot::{MACHINE_ID_FILE, SysRoot};
+
+const PRODUCT_SERIAL_FILE: &str = "/sys/class/dmi/id/product_serial";
+const RANDOM_DEFAULT_LEN: usize = 8;
+
+/// Expands `template`. Each substitution is lowercased and stripped of characters that are not
+/// allowed in host names.
+pub(crate) fn expand(template: &str, root: &SysRoot) -> UResult<String> {
+    let mut host_name = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        host_name.push_str(&rest[..start]);
+        let brace = &rest[start..];
+
+        if let Some(after) = brace
+            .strip_prefix("{{")
+            .or_else(|| brace.strip_prefix("}}"))
+        {
+            host_name.push_str(&brace[..1]);
+            rest = after;
+            continue;
+        }
+
+        let Some(end) = brace.find('}').filter(|_| brace.starts_with('{')) else {
+            return Err(HostNameError::InvalidTemplate("unbalanced braces".into()).into());
+        };
+        host_name.push_str(&substitute(&brace[1..end], root)?);
+        rest = &brace[end + 1..];
+    }
+
+    host_name.push_str(rest);
+    Ok(host_name)
+}
+
+/// Returns the value of a placeholder, given without its braces.
+fn substitute(placeholder: &str, root: &SysRoot) -> UResult<String> {
+    let (name, len) = match placeholder.rsplit_once(':') {
+        Some((name, len)) if len.bytes().all(|b| b.is_ascii_digit()) => {
+            let len = len
+                .parse::<usize>()
+                .ok()
+                .filter(|&len| len > 0)
+                .ok_or_else(|| {
+                    HostNameError::InvalidTemplate(format!("invalid length in {{{placeholder}}}"))
+                })?;
+            (name, Some(len))
+        }
+        _ => (placeholder, None),
+    };
+
+    let (value, keep_last) = match name {
+        "mac" => (mac_address()?, true),
+        "machine-id" => (machine_id(root)?, false),
+        "serial" => (product_serial()?, true),
+        "random" | "random:hex" => (random(b"0123456789abcdef", len)?, false),
+        "random:dec" => (random(b"0123456789", len)?, false),
+        "random:alnum" => (random(b"0123456789abcdefghijklmnopqrstuvwxyz", len)?, false),
+        _ => {
+            return Err(HostNameError::InvalidTemplate(format!(
+                "unknown placeholder {{{placeholder}}}"
+            ))
+            .into());
+        }
+    };
+
+    let value = sanitize_label(&value);
+    let value = match len {
+        Some(len) if keep_last => &value[value.len().saturating_sub(len)..],
+        Some(len) => &value[..len.min(value.len())],
+        None => &value,
+    };
+
+    let value = value.trim_matches('-');
+    if value.is_empty() {
+        return Err(HostNameError::NoTemplateValue(name.to_owned()).into());
+    }
+    Ok(value.to_owned())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn mac_address() -> UResult<String> {
+    use crate::net::{InterfaceAddresses, hardware_address};
+
+    let interface_addresses = InterfaceAddresses::new()?;
+    interface_addresses
+        .iter()
+        .filter(|ifa| ifa.ifa_flags & (libc::IFF_LOOPBACK as libc::c_uint) == 0)
+        .find_map(hardware_address)
+        .map(|address| address.iter().map(|byte| format!("{byte:02x}")).collect())
+        .ok_or_else(|| HostNameError::NoTemplateValue("mac".into()).into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn mac_address() -> UResult<String> {
+    Err(HostNameError::NoTemplateValue("mac".into()).into())
+}
+
+fn machine_id(root: &SysRoot) -> UResult<String> {
+    let machine_id = root
+        .read_name(MACHINE_ID_FILE)?
+        .ok_or_else(|| HostNameError::NoTemplateValue("machine-id".into()))?;
+    Ok(String::from_utf8_lossy(&machine_id).into_owned())
+}
+
+fn product_serial() -> UResult<String> {
+    std::fs::read(PRODUCT_SERIAL_FILE)
+        .map(|serial| String::from_utf8_lossy(&serial).into_owned())
+        .map_err_context(|| format!("cannot read {}", PRODUCT_SERIAL_FILE.quote()))
+}
+
+/// Returns `len` characters drawn uniformly from `alphabet` by the kernel's CSPRNG.
+fn random(alphabet: &[u8], len: Option<usize>) -> UResult<String> {
+    let len = len.unwrap_or(RANDOM_DEFAULT_LEN);
+    // Bytes at or above this limit would favor the first characters of the alphabet.
+    let limit = 256 - 256 % alphabet.len();
+
+    let mut urandom = std::fs::File::open("/dev/urandom")
+        .map_err_context(|| "cannot open /dev/urandom".to_owned())?;
+    let mut value = String::with_capacity(len);
+    let mut buffer = [0_u8; 64];
+
+    while value.len() < len {
+        urandom
+            .read_exact(&mut buffer)
+            .map_err_context(|| "cannot read /dev/urandom".to_owned())?;
+        value.extend(
+            buffer
+                .iter()
+                .filter(|&&byte| usize::from(byte) < limit)
+                .map(|&byte| char::from(alphabet[usize::from(byte) % alphabet.len()]))
+                .take(len - value.len()),
+        );
+    }
+
+    Ok(value)
+}
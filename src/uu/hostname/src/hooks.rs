@@ -0,0 +1,110 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Hooks run before and after the host name changes.
+//!
+//! Hooks are executables in the `pre-change` and `post-change` subdirectories of the hooks
+//! directory, run in the order of their names like run-parts(8) does. They receive the old and
+//! the new host names in `OLD_HOSTNAME` and `NEW_HOSTNAME`.
+
+use std::ffi::{CStr, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult, USimpleError};
+
+use crate::utils::verbose;
+
+pub(crate) const HOOKS_DIR: &str = "/etc/hostname.d";
+
+#[derive(Clone, Copy)]
+pub(crate) enum Stage {
+    PreChange,
+    PostChange,
+}
+
+impl Stage {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::PreChange => "pre-change",
+            Self::PostChange => "post-change",
+        }
+    }
+}
+
+pub(crate) struct Hooks {
+    dir: PathBuf,
+}
+
+impl Hooks {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Runs the hooks of `stage`, stopping at the first one that fails.
+    pub(crate) fn run(&self, stage: Stage, old_name: &CStr, new_name: &CStr) -> UResult<()> {
+        for hook in self.list(stage)? {
+            verbose(1, format_args!("running hook {}", hook.quote()));
+
+            let status = Command::new(&hook)
+                .env("OLD_HOSTNAME", OsStr::from_bytes(old_name.to_bytes()))
+                .env("NEW_HOSTNAME", OsStr::from_bytes(new_name.to_bytes()))
+                .status()
+                .map_err_context(|| format!("cannot run hook {}", hook.quote()))?;
+
+            if !status.success() {
+                return Err(USimpleError::new(
+                    1,
+                    format!("hook {} failed: {status}", hook.quote()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the hooks of `stage`, sorted by name. As with run-parts(8), only executable
+    /// files whose names consist of ASCII letters, digits, underscores and hyphens are hooks,
+    /// which leaves out backup files and package manager leftovers.
+    fn list(&self, stage: Stage) -> UResult<Vec<PathBuf>> {
+        let dir = self.dir.join(stage.dir_name());
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).map_err_context(|| format!("cannot read {}", dir.quote()));
+            }
+        };
+
+        let mut hooks = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err_context(|| format!("cannot read {}", dir.quote()))?
+                .path();
+            if is_hook(&path) {
+                hooks.push(path);
+            }
+        }
+
+        hooks.sort();
+        Ok(hooks)
+    }
+}
+
+fn is_hook(path: &Path) -> bool {
+    let valid_name = path.file_name().is_some_and(|name| {
+        name.as_bytes()
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    });
+
+    // Follow symbolic links, so that hooks can be links to scripts elsewhere.
+    valid_name
+        && std::fs::metadata(path)
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
@@ -3,10 +3,55 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(not(target_family = "windows"))]
+mod audit;
 mod change;
+#[cfg(target_os = "linux")]
+mod cmdline;
+#[cfg(not(target_family = "windows"))]
+mod dbus;
+#[cfg(not(target_family = "windows"))]
+mod dhcp;
+#[cfg(not(target_family = "windows"))]
+mod diff;
+#[cfg(not(target_family = "windows"))]
+mod doctor;
+#[cfg(not(target_family = "windows"))]
+mod env_file;
 mod errors;
+mod escape;
+#[cfg(target_os = "linux")]
+mod exec;
+#[cfg(not(target_family = "windows"))]
+mod export;
+#[cfg(not(target_family = "windows"))]
+mod fcrdns;
+#[cfg(not(target_family = "windows"))]
+mod file_format;
+#[cfg(not(target_family = "windows"))]
+mod format;
+#[cfg(not(target_family = "windows"))]
+mod generate;
+#[cfg(not(target_family = "windows"))]
+mod hooks;
+#[cfg(not(target_family = "windows"))]
+pub mod hostnamectl;
+#[cfg(not(target_family = "windows"))]
+mod hosts;
+#[cfg(not(target_family = "windows"))]
+mod lookup;
+#[cfg(not(target_family = "windows"))]
+mod machine_info;
 mod net;
 mod print;
+#[cfg(not(target_family = "windows"))]
+mod prometheus;
+#[cfg(not(target_family = "windows"))]
+mod resolv_conf;
+#[cfg(not(target_family = "windows"))]
+mod sanitize;
+#[cfg(not(target_family = "windows"))]
+mod sysroot;
 mod utils;
 
 use std::ffi::OsString;
@@ -19,16 +64,48 @@ pub mod options {
     pub static ALIAS: &str = "alias";
     pub static ALL_FQDNS: &str = "all-fqdns";
     pub static ALL_IP_ADDRESSES: &str = "all-ip-addresses";
+    pub static APPLY: &str = "apply";
+    pub static AUDIT_LOG: &str = "audit-log";
+    pub static AUDIT_SYSLOG: &str = "audit-syslog";
     pub static BOOT: &str = "boot";
+    pub static CHECK_FCRDNS: &str = "check-fcrdns";
+    pub static CMDLINE_FILE: &str = "cmdline-file";
+    pub static COMMAND: &str = "command";
+    pub static DOCTOR: &str = "doctor";
     pub static DOMAIN: &str = "domain";
+    pub static DRY_RUN: &str = "dry-run";
+    pub static ESCAPE: &str = "escape";
+    pub static EXEC: &str = "exec";
+    pub static EXPORT: &str = "export";
+    pub static FALLBACK: &str = "fallback";
     pub static FILE: &str = "file";
     pub static FILENAME: &str = "filename";
+    pub static FILE_FORMAT: &str = "file-format";
+    pub static FORMAT: &str = "format";
     pub static FQDN: &str = "fqdn";
+    pub static FQDN_POLICY: &str = "fqdn-policy";
+    pub static FROM_CMDLINE: &str = "from-cmdline";
+    pub static FROM_DHCP: &str = "from-dhcp";
+    pub static GENERATE: &str = "generate";
+    pub static HOOKS_DIR: &str = "hooks-dir";
     pub static HOSTNAME: &str = "hostname";
     pub static IP_ADDRESS: &str = "ip-address";
     pub static LONG: &str = "long";
+    pub static LOOKUP: &str = "lookup";
+    pub static MACHINE_INFO: &str = "machine-info";
+    pub static NETNS: &str = "netns";
+    pub static NETNS_RESOLVE: &str = "netns-resolve";
     pub static NIS: &str = "nis";
+    pub static NIS_DOMAIN: &str = "nis-domain";
+    pub static NO_HOOKS: &str = "no-hooks";
+    pub static NO_LABELS: &str = "no-labels";
+    pub static PROMETHEUS: &str = "prometheus";
+    pub static ROOT: &str = "root";
+    pub static SET_FQDN: &str = "set-fqdn";
+    pub static SANITIZE: &str = "sanitize";
     pub static SHORT: &str = "short";
+    pub static VERBOSE: &str = "verbose";
+    pub static VIA_HOSTNAMED: &str = "via-hostnamed";
     pub static YP: &str = "yp";
 }
 
@@ -38,53 +115,335 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
 
     let _net_lib_guard = net::LibraryGuard::load()?;
 
+    #[cfg(not(target_family = "windows"))]
+    utils::set_verbosity(args.get_count(options::VERBOSE));
+    let escape = escape::Escape::new(args.get_one::<String>(options::ESCAPE).unwrap());
+
+    #[cfg(not(target_family = "windows"))]
+    let audit = audit::Audit::new(
+        args.get_one::<PathBuf>(options::AUDIT_LOG).cloned(),
+        args.get_flag(options::AUDIT_SYSLOG),
+    );
+
+    #[cfg(target_os = "linux")]
+    if let Some(host_name) = args.get_one::<OsString>(options::EXEC) {
+        let command: Vec<OsString> = args
+            .get_many::<OsString>(options::COMMAND)
+            .expect("command must be specified")
+            .cloned()
+            .collect();
+
+        return exec::run(
+            host_name,
+            args.get_one::<OsString>(options::NIS_DOMAIN)
+                .map(OsString::as_os_str),
+            &command,
+            audit,
+        );
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    let root = match args.get_one::<PathBuf>(options::ROOT) {
+        Some(dir) => Some(sysroot::SysRoot::image(dir)?),
+        None => None,
+    };
+
+    #[cfg(not(target_family = "windows"))]
+    if let Some(format) = args.get_one::<String>(options::DOCTOR) {
+        return doctor::run(format, &escape);
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if args.get_flag(options::CHECK_FCRDNS) {
+        return fcrdns::run(&escape);
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if let Some(name) = args.get_one::<OsString>(options::LOOKUP) {
+        return lookup::run(name, &escape);
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if let Some(shell) = args.get_one::<String>(options::EXPORT) {
+        return export::run(shell, args.get_flag(options::FALLBACK));
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if args.get_flag(options::PROMETHEUS) {
+        return prometheus::run(args.get_flag(options::FALLBACK));
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if let Some(template) = args.get_one::<String>(options::FORMAT) {
+        return format::run(template, args.get_flag(options::FALLBACK), &escape);
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    if args.contains_id(options::MACHINE_INFO) {
+        let root = root.unwrap_or_default();
+        return machine_info::run(&root, args.get_one::<String>(options::MACHINE_INFO));
+    }
+
+    // Connecting to the system bus can block, so it is only done where hostnamed is used.
+    #[cfg(not(target_family = "windows"))]
+    let connect_hostnamed = || {
+        dbus::Hostnamed::connect(
+            args.get_one::<String>(options::VIA_HOSTNAMED)
+                .map_or(dbus::ViaHostnamed::Never, |when| {
+                    dbus::ViaHostnamed::from_name(when)
+                }),
+        )
+    };
+
     if args.contains_id("set-group") {
+        let settings = change::Settings {
+            boot: args.get_flag(options::BOOT),
+            dry_run: args.get_flag(options::DRY_RUN),
+            #[cfg(not(target_family = "windows"))]
+            preview: args.contains_id(options::GENERATE)
+                && !args.get_flag(options::APPLY)
+                && !args.get_flag(options::DRY_RUN),
+            // Hooks act on the running system, so they are not run for an offline image.
+            #[cfg(not(target_family = "windows"))]
+            hooks: (root.is_none() && !args.get_flag(options::NO_HOOKS)).then(|| {
+                hooks::Hooks::new(
+                    args.get_one::<PathBuf>(options::HOOKS_DIR)
+                        .cloned()
+                        .unwrap_or_else(|| PathBuf::from(hooks::HOOKS_DIR)),
+                )
+            }),
+            #[cfg(not(target_family = "windows"))]
+            audit,
+            #[cfg(not(target_family = "windows"))]
+            source: if args.contains_id(options::GENERATE) {
+                change::Source::Generated
+            } else if args.contains_id(options::FROM_DHCP) {
+                change::Source::Dhcp
+            } else if args.contains_id(options::FILE) {
+                change::Source::File
+            } else {
+                change::Source::Argument
+            },
+            #[cfg(not(target_family = "windows"))]
+            root: root.unwrap_or_default(),
+            #[cfg(not(target_family = "windows"))]
+            hostnamed: connect_hostnamed()?,
+            #[cfg(not(target_family = "windows"))]
+            sanitize: args.get_flag(options::SANITIZE),
+            #[cfg(not(target_family = "windows"))]
+            file_format: args
+                .get_one::<file_format::FileFormat>(options::FILE_FORMAT)
+                .cloned()
+                .unwrap_or_default(),
+        };
+
+        #[cfg(not(target_family = "windows"))]
+        if let Some(fqdn) = args.get_one::<OsString>(options::SET_FQDN) {
+            let policy = args
+                .get_one::<String>(options::FQDN_POLICY)
+                .map_or(change::FqdnPolicy::Hosts, |name| {
+                    change::FqdnPolicy::from_name(name)
+                });
+            return change::set_fqdn(fqdn, policy, &settings);
+        }
+
+        #[cfg(not(target_family = "windows"))]
+        if let Some(template) = args.get_one::<String>(options::GENERATE) {
+            let host_name = generate::expand(template, &settings.root)?;
+            return change::from_argument(host_name.as_ref(), &settings);
+        }
+
+        #[cfg(not(target_family = "windows"))]
+        if args.contains_id(options::FROM_DHCP) {
+            let source = args.get_one::<OsString>(options::FROM_DHCP);
+            let host_name = dhcp::host_name(&settings.root, source.map(OsString::as_os_str))?;
+            return change::from_argument(host_name.as_ref(), &settings);
+        }
+
+        #[cfg(target_os = "linux")]
+        if args.get_flag(options::FROM_CMDLINE) {
+            let path = args.get_one::<PathBuf>(options::CMDLINE_FILE).map_or(
+                std::path::Path::new(cmdline::PROC_CMDLINE),
+                PathBuf::as_path,
+            );
+
+            if let Some(host_name) = cmdline::host_name(path)? {
+                let settings = change::Settings {
+                    source: change::Source::KernelCommandLine,
+                    ..settings
+                };
+                return change::from_argument(&host_name, &settings);
+            } else if !args.contains_id(options::FILE) {
+                return if settings.boot {
+                    change::from_argument(
+                        std::ffi::OsStr::new(change::DEFAULT_HOST_NAME),
+                        &settings,
+                    )
+                } else {
+                    Err(errors::HostNameError::NoCmdlineHostName.into())
+                };
+            }
+        }
+
         if let Some(path) = args.get_one::<PathBuf>(options::FILE) {
-            change::from_file(path)
+            change::from_file(path, &settings)
         } else {
             let host_name = args
                 .get_one::<OsString>(options::HOSTNAME)
                 .expect("hostname must be specified");
 
-            change::from_argument(host_name)
+            change::from_argument(host_name, &settings)
         }
     } else {
-        let host_name: &mut dyn print::PrintHostName = if args.get_flag(options::ALIAS) {
-            &mut print::AliasHostName
-        } else if args.get_flag(options::DOMAIN) {
-            &mut print::DomainHostName
-        } else if args.get_flag(options::FQDN) {
-            &mut print::FqdnHostName
-        } else if args.get_flag(options::ALL_FQDNS) {
-            &mut print::AllFqdnHostName
-        } else if args.get_flag(options::IP_ADDRESS) {
-            &mut print::IpAddressHostName
-        } else if args.get_flag(options::ALL_IP_ADDRESSES) {
-            &mut print::AllIpAddressesHostName
-        } else if args.get_flag(options::SHORT) {
-            &mut print::ShortHostName
-        } else if args.get_flag(options::NIS) {
-            &mut print::NisHostName
-        } else {
-            &mut print::DefaultHostName
+        // Several queries are answered in the order of their flags.
+        let mut queries: Vec<&str> = GET_OPTIONS
+            .into_iter()
+            .filter(|&id| args.get_flag(id))
+            .collect();
+        queries.sort_by_key(|&id| args.index_of(id));
+        let labels = !args.get_flag(options::NO_LABELS);
+
+        #[cfg(not(target_family = "windows"))]
+        if let Some(root) = root {
+            let query = |id| match id {
+                id if id == options::ALIAS => print::StaticQuery::Alias,
+                id if id == options::DOMAIN => print::StaticQuery::Domain,
+                id if id == options::FQDN => print::StaticQuery::Fqdn,
+                id if id == options::IP_ADDRESS => print::StaticQuery::IpAddress,
+                id if id == options::SHORT => print::StaticQuery::Short,
+                id if id == options::NIS => print::StaticQuery::Nis,
+                _ => print::StaticQuery::HostName,
+            };
+
+            let printers: Vec<(&str, Box<dyn print::PrintHostName>)> = if queries.is_empty() {
+                let host_name = print::StaticHostName {
+                    root,
+                    query: print::StaticQuery::HostName,
+                };
+                vec![("", Box::new(host_name))]
+            } else {
+                queries
+                    .iter()
+                    .map(|&id| -> (&str, Box<dyn print::PrintHostName>) {
+                        let host_name = print::StaticHostName {
+                            root: root.clone(),
+                            query: query(id),
+                        };
+                        (id, Box::new(host_name))
+                    })
+                    .collect()
+            };
+            return print::print_all(&printers, labels, &escape);
+        }
+
+        #[cfg(not(target_family = "windows"))]
+        let lookup = std::rc::Rc::new(print::HostLookup::default());
+        #[cfg(not(target_family = "windows"))]
+        let fallback = args.get_flag(options::FALLBACK);
+
+        #[cfg(target_os = "linux")]
+        let open_netns = || {
+            args.get_one::<OsString>(options::NETNS)
+                .map(|name| net::NetworkNamespace::open(name))
+                .transpose()
         };
 
-        let mut stdout = std::io::stdout();
-        host_name.print_host_name(&mut stdout)
+        let mut printers: Vec<(&str, Box<dyn print::PrintHostName>)> = Vec::new();
+        for id in queries {
+            let host_name: Box<dyn print::PrintHostName> = match id {
+                id if id == options::ALIAS => Box::new(print::AliasHostName {
+                    #[cfg(not(target_family = "windows"))]
+                    lookup: lookup.clone(),
+                }),
+                id if id == options::DOMAIN => Box::new(print::DomainHostName {
+                    #[cfg(not(target_family = "windows"))]
+                    lookup: lookup.clone(),
+                    #[cfg(not(target_family = "windows"))]
+                    fallback,
+                }),
+                id if id == options::FQDN => Box::new(print::FqdnHostName {
+                    #[cfg(not(target_family = "windows"))]
+                    lookup: lookup.clone(),
+                    #[cfg(not(target_family = "windows"))]
+                    fallback,
+                }),
+                id if id == options::ALL_FQDNS => Box::new(print::AllFqdnHostName {
+                    #[cfg(target_os = "linux")]
+                    netns: open_netns()?,
+                    #[cfg(target_os = "linux")]
+                    netns_resolve: args.get_flag(options::NETNS_RESOLVE),
+                }),
+                id if id == options::IP_ADDRESS => Box::new(print::IpAddressHostName {
+                    #[cfg(not(target_family = "windows"))]
+                    lookup: lookup.clone(),
+                    #[cfg(not(target_family = "windows"))]
+                    fallback,
+                }),
+                id if id == options::ALL_IP_ADDRESSES => Box::new(print::AllIpAddressesHostName {
+                    #[cfg(target_os = "linux")]
+                    netns: open_netns()?,
+                }),
+                id if id == options::SHORT => Box::new(print::ShortHostName),
+                _ => Box::new(print::NisHostName),
+            };
+            printers.push((id, host_name));
+        }
+
+        if printers.is_empty() {
+            let host_name = print::DefaultHostName {
+                #[cfg(not(target_family = "windows"))]
+                hostnamed: connect_hostnamed()?,
+            };
+            printers.push(("", Box::new(host_name)));
+        }
+
+        print::print_all(&printers, labels, &escape)
     }
 }
 
+/// The flags of the `get-group`, whose names label their answers.
+const GET_OPTIONS: [&str; 8] = [
+    options::ALIAS,
+    options::DOMAIN,
+    options::FQDN,
+    options::ALL_FQDNS,
+    options::IP_ADDRESS,
+    options::ALL_IP_ADDRESSES,
+    options::SHORT,
+    options::NIS,
+];
+
 #[must_use]
 pub fn uu_app() -> Command {
-    let usage = "hostname [-a|--alias|-d|--domain|-f|--fqdn|--long|-A|--all-fqdns|-i|--ip-address|-I|--all-ip-addresses|-s|--short|-y|--yp|--nis]
-hostname [-b|--boot] {-F filename|--file filename|hostname}
+    let mut usage = String::from(
+        "hostname [-a|--alias|-d|--domain|-f|--fqdn|--long|-A|--all-fqdns|-i|--ip-address|-I|--all-ip-addresses|-s|--short|-y|--yp|--nis]...
+hostname [-b|--boot] {-F filename|--file filename|hostname}",
+    );
+    #[cfg(not(target_family = "windows"))]
+    usage.push_str(
+        "
+hostname [--dry-run] {--set-fqdn FQDN|--from-dhcp[=IFACE|LEASEFILE]|--generate TEMPLATE}
+hostname {--format STRING|--export[=SHELL]|--prometheus}
+hostname {--lookup NAME|--doctor[=FORMAT]|--check-fcrdns}
+hostname --machine-info [KEY[=VALUE]]",
+    );
+    #[cfg(target_os = "linux")]
+    usage.push_str(
+        "
+hostname --from-cmdline [-b|--boot|-F filename|--file filename]
+hostname --exec NAME [--nis-domain DOMAIN] -- COMMAND [ARG]...
+hostname --netns NAME [-A|--all-fqdns|-I|--all-ip-addresses]...",
+    );
+    usage.push_str(
+        "
 hostname {-h|--help}
-hostname {-V|--version}";
+hostname {-V|--version}",
+    );
 
-    Command::new(uucore::util_name())
+    let command = Command::new(uucore::util_name())
         .version(crate_version!())
         .about("Show or set the system's host name")
-        .override_usage(format_usage(usage))
+        .override_usage(format_usage(&usage))
         .infer_long_args(true)
         .arg(
             Arg::new(options::ALIAS)
@@ -144,6 +503,25 @@ hostname {-V|--version}";
                 .action(ArgAction::SetTrue)
                 .help("NIS/YP domain name"),
         )
+        .arg(
+            Arg::new(options::NO_LABELS)
+                .long(options::NO_LABELS)
+                .action(ArgAction::SetTrue)
+                .requires("get-group")
+                .help("with several of the options above, print only the answers, one per line"),
+        )
+        .arg(
+            Arg::new(options::ESCAPE)
+                .long(options::ESCAPE)
+                .value_name("WHEN")
+                .value_parser(["auto", "never", "c", "shell"])
+                .default_value("auto")
+                .help(
+                    "print control and non-UTF-8 bytes of names as \\xNN escapes (c), quote \
+                     names for a shell (shell), or print them as they are (never); auto \
+                     escapes them when standard output is a terminal",
+                ),
+        )
         .arg(
             Arg::new(options::BOOT)
                 .short('b')
@@ -151,6 +529,16 @@ hostname {-V|--version}";
                 .action(ArgAction::SetTrue)
                 .help("set default hostname if none available"),
         )
+        .arg(
+            Arg::new(options::DRY_RUN)
+                .long(options::DRY_RUN)
+                .action(ArgAction::SetTrue)
+                .requires("set-group")
+                .help(
+                    "print what would change, including file edits, without changing anything; \
+                     exit with status 2 if nothing would change",
+                ),
+        )
         .arg(
             Arg::new(options::FILE)
                 .short('F')
@@ -159,7 +547,9 @@ hostname {-V|--version}";
                 .value_parser(value_parser!(PathBuf))
                 .action(ArgAction::Set)
                 .conflicts_with(options::HOSTNAME)
-                .help("read host name or NIS domain name from given file"),
+                .help(
+                    "read host name or NIS domain name from given file, or '-' for standard input",
+                ),
         )
         .arg(
             Arg::new(options::HOSTNAME)
@@ -168,17 +558,8 @@ hostname {-V|--version}";
         )
         .group(
             ArgGroup::new("get-group")
-                .args([
-                    options::ALIAS,
-                    options::DOMAIN,
-                    options::FQDN,
-                    options::ALL_FQDNS,
-                    options::IP_ADDRESS,
-                    options::ALL_IP_ADDRESSES,
-                    options::SHORT,
-                    options::NIS,
-                ])
-                .multiple(false)
+                .args(GET_OPTIONS)
+                .multiple(true)
                 .conflicts_with("set-group"),
         )
         .group(
@@ -192,5 +573,347 @@ hostname {-V|--version}";
             ArgGroup::new("source-group")
                 .args([options::FILE, options::HOSTNAME])
                 .multiple(false),
+        );
+
+    #[cfg(not(target_family = "windows"))]
+    let command = command
+        .arg(
+            Arg::new(options::ROOT)
+                .long(options::ROOT)
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with_all([options::ALL_FQDNS, options::ALL_IP_ADDRESSES])
+                .help(
+                    "use the static configuration of the OS image in DIR instead of the running \
+                     system",
+                ),
+        )
+        .arg(
+            Arg::new(options::MACHINE_INFO)
+                .long(options::MACHINE_INFO)
+                .value_name("KEY[=VALUE]")
+                .value_parser(value_parser!(String))
+                .num_args(0..=1)
+                .conflicts_with_all(["get-group", "set-group"])
+                .help("show all /etc/machine-info properties, or show or set one of them"),
+        )
+        .arg(
+            Arg::new(options::DOCTOR)
+                .long(options::DOCTOR)
+                .value_name("FORMAT")
+                .value_parser(["text", "json"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("text")
+                .conflicts_with_all(["get-group", "set-group", options::ROOT])
+                .help(
+                    "check that the kernel, /etc/hostname, /etc/hosts, $HOSTNAME and DNS agree \
+                     on the host name",
+                ),
+        )
+        .arg(
+            Arg::new(options::CHECK_FCRDNS)
+                .long(options::CHECK_FCRDNS)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["get-group", "set-group", options::ROOT, options::DOCTOR])
+                .help(
+                    "check that the reverse DNS name of each address printed by -I resolves \
+                     back to that address",
+                ),
+        )
+        .arg(
+            Arg::new(options::LOOKUP)
+                .long(options::LOOKUP)
+                .value_name("NAME|ADDRESS")
+                .value_parser(value_parser!(OsString))
+                .conflicts_with_all([
+                    "get-group",
+                    "set-group",
+                    options::ROOT,
+                    options::DOCTOR,
+                    options::CHECK_FCRDNS,
+                ])
+                .help(
+                    "print the canonical name, the addresses and their reverse DNS names of \
+                     NAME|ADDRESS, as resolved for -f, -i and -a",
+                ),
+        )
+        .arg(
+            Arg::new(options::FALLBACK)
+                .long(options::FALLBACK)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::ROOT)
+                .help(
+                    "when the host name does not resolve, make -i print the interface addresses, \
+                     and -d and -f use the local domain of the resolver",
+                ),
         )
+        .arg(
+            Arg::new(options::FORMAT)
+                .long(options::FORMAT)
+                .value_name("STRING")
+                .value_parser(value_parser!(String))
+                .conflicts_with_all([
+                    "get-group",
+                    "set-group",
+                    options::ROOT,
+                    options::DOCTOR,
+                    options::CHECK_FCRDNS,
+                    options::LOOKUP,
+                ])
+                .help(
+                    "print STRING with %h, %s, %f, %d, %y, %i and %I replaced by the host name, \
+                     short name, FQDN, DNS domain, NIS domain, addresses and interface \
+                     addresses, and \\n, \\t, \\0 and \\\\ by the characters they stand for",
+                ),
+        )
+        .arg(
+            Arg::new(options::EXPORT)
+                .long(options::EXPORT)
+                .value_name("SHELL")
+                .value_parser(["sh", "fish", "pwsh"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("sh")
+                .conflicts_with_all([
+                    "get-group",
+                    "set-group",
+                    options::ROOT,
+                    options::DOCTOR,
+                    options::CHECK_FCRDNS,
+                    options::LOOKUP,
+                    options::FORMAT,
+                ])
+                .help(
+                    "print assignments of HOSTNAME, HOSTNAME_SHORT, FQDN, DNSDOMAIN, NISDOMAIN, \
+                     IP_ADDRESSES and the machine description from /etc/machine-info to \
+                     evaluate in a POSIX shell, fish or PowerShell",
+                ),
+        )
+        .arg(
+            Arg::new(options::PROMETHEUS)
+                .long(options::PROMETHEUS)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "get-group",
+                    "set-group",
+                    options::ROOT,
+                    options::DOCTOR,
+                    options::CHECK_FCRDNS,
+                    options::LOOKUP,
+                    options::FORMAT,
+                    options::EXPORT,
+                ])
+                .help(
+                    "print the host names, the address counts of the interfaces, and the \
+                     duration and success of each lookup as Prometheus metrics",
+                ),
+        )
+        .arg(
+            Arg::new(options::VIA_HOSTNAMED)
+                .long(options::VIA_HOSTNAMED)
+                .value_name("WHEN")
+                .value_parser(["always", "auto", "never"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("always")
+                .conflicts_with(options::ROOT)
+                .help("get or set the host name through systemd-hostnamed"),
+        )
+        .arg(
+            Arg::new(options::FILE_FORMAT)
+                .long(options::FILE_FORMAT)
+                .value_name("FORMAT")
+                .value_parser(file_format::FileFormat::from_name)
+                .requires(options::FILE)
+                .help(
+                    "how to find the name in the file: plain, shell-var:NAME, yaml-key:NAME \
+                     or auto (default: plain)",
+                ),
+        )
+        .arg(
+            Arg::new(options::FROM_DHCP)
+                .long(options::FROM_DHCP)
+                .value_name("IFACE|LEASEFILE")
+                .value_parser(value_parser!(OsString))
+                .num_args(0..=1)
+                .require_equals(true)
+                .conflicts_with_all([options::FILE, options::HOSTNAME])
+                .help("set the host name assigned by the newest current DHCP lease"),
+        )
+        .arg(
+            Arg::new(options::GENERATE)
+                .long(options::GENERATE)
+                .value_name("TEMPLATE")
+                .value_parser(value_parser!(String))
+                .conflicts_with_all([options::FILE, options::HOSTNAME, options::FROM_DHCP])
+                .help(
+                    "print a host name made from TEMPLATE, with placeholders {mac[:N]}, \
+                     {machine-id[:N]}, {serial[:N]} and {random[:hex|dec|alnum][:N]}",
+                ),
+        )
+        .arg(
+            Arg::new(options::APPLY)
+                .long(options::APPLY)
+                .action(ArgAction::SetTrue)
+                .requires(options::GENERATE)
+                .help("with --generate, also set the generated host name"),
+        )
+        .arg(
+            Arg::new(options::SANITIZE)
+                .long(options::SANITIZE)
+                .action(ArgAction::SetTrue)
+                .requires("set-group")
+                .help("turn the new host name into a valid one instead of rejecting it"),
+        )
+        .arg(
+            Arg::new(options::HOOKS_DIR)
+                .long(options::HOOKS_DIR)
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .requires("set-group")
+                .help(
+                    "run the hooks in DIR/pre-change and DIR/post-change around the change \
+                     (default: /etc/hostname.d)",
+                ),
+        )
+        .arg(
+            Arg::new(options::NO_HOOKS)
+                .long(options::NO_HOOKS)
+                .action(ArgAction::SetTrue)
+                .requires("set-group")
+                .conflicts_with(options::HOOKS_DIR)
+                .help("do not run the pre-change and post-change hooks"),
+        )
+        .arg(
+            Arg::new(options::AUDIT_LOG)
+                .long(options::AUDIT_LOG)
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("append a JSON line describing each name change, and its result, to FILE"),
+        )
+        .arg(
+            Arg::new(options::AUDIT_SYSLOG)
+                .long(options::AUDIT_SYSLOG)
+                .action(ArgAction::SetTrue)
+                .help("also report each name change to syslog"),
+        )
+        .arg(
+            Arg::new(options::VERBOSE)
+                .short('v')
+                .long(options::VERBOSE)
+                .action(ArgAction::Count)
+                .help(
+                    "explain what is being done, tracing each call to the resolver with its \
+                     timing; repeat for more details",
+                ),
+        )
+        .arg(
+            Arg::new(options::SET_FQDN)
+                .long(options::SET_FQDN)
+                .value_name("FQDN")
+                .value_parser(value_parser!(OsString))
+                .conflicts_with_all([
+                    options::FILE,
+                    options::HOSTNAME,
+                    options::FROM_DHCP,
+                    options::GENERATE,
+                ])
+                .help("set the first label of FQDN as the host name, and record FQDN as well"),
+        )
+        .arg(
+            Arg::new(options::FQDN_POLICY)
+                .long(options::FQDN_POLICY)
+                .value_name("WHERE")
+                .value_parser(["hosts", "hostname-file", "both"])
+                .requires(options::SET_FQDN)
+                .help("where --set-fqdn records the FQDN (default: hosts)"),
+        )
+        .mut_group("set-group", |group| {
+            group.args([options::FROM_DHCP, options::GENERATE, options::SET_FQDN])
+        })
+        .mut_group("source-group", |group| {
+            group.args([options::FROM_DHCP, options::GENERATE, options::SET_FQDN])
+        });
+
+    #[cfg(target_os = "linux")]
+    let command = command
+        .arg(
+            Arg::new(options::NETNS)
+                .long(options::NETNS)
+                .value_name("NAME")
+                .value_parser(value_parser!(OsString))
+                .requires("interfaces-group")
+                .conflicts_with(options::ROOT)
+                .help("list interface addresses of the given network namespace"),
+        )
+        .arg(
+            Arg::new(options::NETNS_RESOLVE)
+                .long(options::NETNS_RESOLVE)
+                .action(ArgAction::SetTrue)
+                .requires(options::NETNS)
+                .help("also resolve addresses inside the network namespace"),
+        )
+        .arg(
+            Arg::new(options::EXEC)
+                .long(options::EXEC)
+                .value_name("NAME")
+                .value_parser(value_parser!(OsString))
+                .requires(options::COMMAND)
+                .conflicts_with_all(["get-group", "set-group", options::ROOT])
+                .help("run a command in a new UTS namespace with the given host name"),
+        )
+        .arg(
+            Arg::new(options::NIS_DOMAIN)
+                .long(options::NIS_DOMAIN)
+                .value_name("DOMAIN")
+                .value_parser(value_parser!(OsString))
+                .requires(options::EXEC)
+                .help(
+                    "with --exec, also set the NIS/YP domain name (not --domain, since -d and \
+                     --domain print the DNS domain)",
+                ),
+        )
+        .arg(
+            Arg::new(options::COMMAND)
+                .value_name("COMMAND")
+                .value_parser(value_parser!(OsString))
+                .num_args(1..)
+                .last(true)
+                .requires(options::EXEC),
+        )
+        .arg(
+            Arg::new(options::FROM_CMDLINE)
+                .long(options::FROM_CMDLINE)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    options::HOSTNAME,
+                    options::FROM_DHCP,
+                    options::GENERATE,
+                    options::SET_FQDN,
+                ])
+                .help(
+                    "set the host name given on the kernel command line; with -F or -b, \
+                     fall back to the file, then to localhost",
+                ),
+        )
+        .arg(
+            Arg::new(options::CMDLINE_FILE)
+                .long(options::CMDLINE_FILE)
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .requires(options::FROM_CMDLINE)
+                .help("read the kernel command line from PATH instead of /proc/cmdline"),
+        )
+        .mut_group("set-group", |group| group.arg(options::FROM_CMDLINE))
+        .mut_group("source-group", |group| {
+            group.arg(options::FROM_CMDLINE).multiple(true)
+        })
+        .group(
+            ArgGroup::new("interfaces-group")
+                .args([options::ALL_FQDNS, options::ALL_IP_ADDRESSES])
+                .multiple(true),
+        );
+
+    command
 }
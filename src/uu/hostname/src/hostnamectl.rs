@@ -0,0 +1,454 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! A `hostnamectl` work-alike that also works on systems without systemd-hostnamed.
+//!
+//! The static host name lives in `/etc/hostname`, the transient host name is the one of the
+//! running kernel, and the pretty host name is `PRETTY_HOSTNAME` in `/etc/machine-info`.
+//! When systemd-hostnamed is available, it is asked to do the work instead.
+
+// spell-checker:ignore hostnamectl hostnamed
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, ArgMatches, Command, crate_version, value_parser};
+use uucore::error::UResult;
+use uucore::format_usage;
+
+use crate::change::unix::{validate_host_name, write_static_host_name};
+use crate::dbus::{Hostnamed, ViaHostnamed};
+use crate::errors::HostNameError;
+use crate::machine_info::{
+    CHASSIS, DEPLOYMENT, HARDWARE_MODEL, HARDWARE_VENDOR, ICON_NAME, LOCATION, MachineInfo,
+    PRETTY_HOSTNAME,
+};
+use crate::net;
+use crate::sysroot::{HOSTNAME_FILE, SysRoot};
+
+pub mod options {
+    pub static HOSTNAME: &str = "hostname";
+    pub static NAME: &str = "name";
+    pub static PRETTY: &str = "pretty";
+    pub static ROOT: &str = "root";
+    pub static STATIC: &str = "static";
+    pub static STATUS: &str = "status";
+    pub static TRANSIENT: &str = "transient";
+    pub static VALUE: &str = "value";
+    pub static VIA_HOSTNAMED: &str = "via-hostnamed";
+}
+
+/// Commands that query or change a single `/etc/machine-info` property.
+const PROPERTY_COMMANDS: [(&str, &str); 4] = [
+    ("icon-name", ICON_NAME),
+    ("chassis", CHASSIS),
+    ("deployment", DEPLOYMENT),
+    ("location", LOCATION),
+];
+
+/// The kinds of host names selected on the command line.
+struct Selection {
+    static_: bool,
+    transient: bool,
+    pretty: bool,
+}
+
+impl Selection {
+    fn from_matches(args: &ArgMatches) -> Self {
+        let selection = Self {
+            static_: args.get_flag(options::STATIC),
+            transient: args.get_flag(options::TRANSIENT),
+            pretty: args.get_flag(options::PRETTY),
+        };
+
+        if selection.is_empty() {
+            Self {
+                static_: true,
+                transient: true,
+                pretty: true,
+            }
+        } else {
+            selection
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.static_ || self.transient || self.pretty)
+    }
+
+    fn is_all(&self) -> bool {
+        self.static_ && self.transient && self.pretty
+    }
+}
+
+#[uucore::main]
+pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let args = uu_app().try_get_matches_from(args)?;
+
+    let system = match args.get_one::<PathBuf>(options::ROOT) {
+        Some(dir) => System {
+            root: SysRoot::image(dir)?,
+            hostnamed: None,
+        },
+        None => System {
+            root: SysRoot::default(),
+            hostnamed: Hostnamed::connect(
+                args.get_one::<String>(options::VIA_HOSTNAMED)
+                    .map_or(ViaHostnamed::Auto, |when| ViaHostnamed::from_name(when)),
+            )?,
+        },
+    };
+
+    match args.subcommand() {
+        Some((name, sub_args)) if name == options::HOSTNAME => {
+            let selection = Selection::from_matches(sub_args);
+            if let Some(name) = sub_args.get_one::<String>(options::NAME) {
+                system.set_host_names(name, &selection)
+            } else {
+                system.print_host_name(&selection)
+            }
+        }
+
+        Some((name, sub_args)) if name == options::STATUS => system.status(sub_args),
+
+        Some((name, sub_args)) => {
+            let key = PROPERTY_COMMANDS
+                .iter()
+                .find_map(|&(command, key)| (command == name).then_some(key))
+                .expect("unknown command");
+            system.machine_info_property(key, sub_args.get_one::<String>(options::VALUE))
+        }
+
+        None => system.status(&args),
+    }
+}
+
+/// Where the host names are read from and written to: systemd-hostnamed when it is used, the
+/// files and the kernel otherwise.
+struct System {
+    root: SysRoot,
+    hostnamed: Option<Hostnamed>,
+}
+
+impl System {
+    fn static_host_name(&self) -> UResult<Option<String>> {
+        if let Some(hostnamed) = &self.hostnamed {
+            let host_name = hostnamed.get("StaticHostname")?;
+            return Ok((!host_name.is_empty()).then_some(host_name));
+        }
+
+        let host_name = self.root.read_name(HOSTNAME_FILE)?;
+        Ok(host_name.map(|name| String::from_utf8_lossy(&name).into_owned()))
+    }
+
+    fn transient_host_name(&self) -> UResult<String> {
+        if let Some(hostnamed) = &self.hostnamed {
+            return hostnamed.get("Hostname");
+        }
+
+        Ok(net::host_name()?.to_string_lossy().into_owned())
+    }
+
+    /// Returns the value of an `/etc/machine-info` property, or `None` if it is not set.
+    fn property(&self, machine_info: &MachineInfo, key: &str) -> UResult<Option<String>> {
+        if let Some(hostnamed) = &self.hostnamed {
+            let value = hostnamed.get(hostnamed_property(key))?;
+            return Ok((!value.is_empty()).then_some(value));
+        }
+
+        Ok(machine_info.get(key).map(str::to_owned))
+    }
+
+    fn machine_info(&self) -> UResult<MachineInfo> {
+        if self.hostnamed.is_some() {
+            Ok(MachineInfo::default())
+        } else {
+            MachineInfo::read(&self.root)
+        }
+    }
+
+    fn status(&self, args: &ArgMatches) -> UResult<()> {
+        let selection = Selection::from_matches(args);
+        let machine_info = self.machine_info()?;
+
+        if !selection.is_all() {
+            // Legacy mode: print the selected names only.
+            if selection.static_ {
+                println!("{}", self.static_host_name()?.unwrap_or_default());
+            }
+            if selection.transient {
+                println!("{}", self.transient_host_name()?);
+            }
+            if selection.pretty {
+                let pretty_name = self.property(&machine_info, PRETTY_HOSTNAME)?;
+                println!("{}", pretty_name.unwrap_or_default());
+            }
+            return Ok(());
+        }
+
+        let static_name = self.static_host_name()?;
+        println!(
+            "{:>18}: {}",
+            "Static hostname",
+            static_name.as_deref().unwrap_or("n/a")
+        );
+
+        if !self.root.is_image() {
+            let transient_name = self.transient_host_name()?;
+            if static_name.as_ref() != Some(&transient_name) {
+                println!("{:>18}: {transient_name}", "Transient hostname");
+            }
+        }
+
+        let fields = [
+            ("Pretty hostname", PRETTY_HOSTNAME),
+            ("Icon name", ICON_NAME),
+            ("Chassis", CHASSIS),
+            ("Deployment", DEPLOYMENT),
+            ("Location", LOCATION),
+            ("Hardware Vendor", HARDWARE_VENDOR),
+            ("Hardware Model", HARDWARE_MODEL),
+        ];
+        for (label, key) in fields {
+            if let Some(value) = self.property(&machine_info, key)? {
+                println!("{label:>18}: {value}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prints or changes a single `/etc/machine-info` property, as the `chassis`, `deployment`,
+    /// `location` and `icon-name` commands do.
+    fn machine_info_property(&self, key: &str, value: Option<&String>) -> UResult<()> {
+        if let Some(hostnamed) = &self.hostnamed {
+            return match value {
+                Some(value) => hostnamed.set(&format!("Set{}", hostnamed_property(key)), value),
+                None => {
+                    println!("{}", hostnamed.get(hostnamed_property(key))?);
+                    Ok(())
+                }
+            };
+        }
+
+        let mut machine_info = MachineInfo::read(&self.root)?;
+
+        if let Some(value) = value {
+            machine_info.set(key, value)?;
+            machine_info.write(&self.root)
+        } else {
+            println!("{}", machine_info.get(key).unwrap_or_default());
+            Ok(())
+        }
+    }
+
+    fn print_host_name(&self, selection: &Selection) -> UResult<()> {
+        let name = if selection.pretty && !selection.static_ {
+            let machine_info = self.machine_info()?;
+            self.property(&machine_info, PRETTY_HOSTNAME)?
+                .unwrap_or_default()
+        } else if selection.static_ && !selection.transient || self.root.is_image() {
+            self.static_host_name()?.unwrap_or_default()
+        } else {
+            self.transient_host_name()?
+        };
+
+        println!("{name}");
+        Ok(())
+    }
+
+    fn set_host_names(&self, name: &str, selection: &Selection) -> UResult<()> {
+        // Like hostnamed: when the pretty name is set as well, the other names are derived from
+        // it. Otherwise the user is assumed to know what they are doing and the name is used as
+        // is.
+        let host_name = if selection.pretty {
+            derive_host_name(name)
+        } else {
+            name.to_owned()
+        };
+
+        let host_name = if host_name.is_empty() {
+            None
+        } else {
+            Some(validate_host_name(Cow::Borrowed(host_name.as_bytes()))?)
+        };
+
+        let pretty_name =
+            if selection.is_all() && validate_host_name(Cow::Borrowed(name.as_bytes())).is_ok() {
+                "" // The pretty name would be redundant with the host name.
+            } else {
+                name
+            };
+
+        if let Some(hostnamed) = &self.hostnamed {
+            let host_name = host_name
+                .as_deref()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+
+            if selection.static_ {
+                hostnamed.set("SetStaticHostname", &host_name)?;
+            }
+            if selection.transient {
+                if host_name.is_empty() {
+                    return Err(HostNameError::InvalidHostName.into());
+                }
+                hostnamed.set("SetHostname", &host_name)?;
+            }
+            if selection.pretty {
+                hostnamed.set("SetPrettyHostname", pretty_name)?;
+            }
+            return Ok(());
+        }
+
+        let machine_info = if selection.pretty {
+            let mut machine_info = MachineInfo::read(&self.root)?;
+            machine_info.set(PRETTY_HOSTNAME, pretty_name)?;
+            Some(machine_info)
+        } else {
+            None
+        };
+
+        if selection.transient && !self.root.is_image() {
+            let host_name = host_name.as_deref().ok_or(HostNameError::InvalidHostName)?;
+            net::set_host_name(host_name)?;
+        }
+
+        if selection.static_ {
+            write_static_host_name(&self.root, host_name.as_deref())?;
+        }
+
+        if let Some(machine_info) = machine_info {
+            machine_info.write(&self.root)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the hostnamed property that mirrors an `/etc/machine-info` property.
+/// The method that changes it is the same name, prefixed with `Set`.
+fn hostnamed_property(key: &str) -> &'static str {
+    match key {
+        PRETTY_HOSTNAME => "PrettyHostname",
+        ICON_NAME => "IconName",
+        CHASSIS => "Chassis",
+        DEPLOYMENT => "Deployment",
+        LOCATION => "Location",
+        HARDWARE_VENDOR => "HardwareVendor",
+        HARDWARE_MODEL => "HardwareModel",
+        _ => unreachable!("no hostnamed property for {key}"),
+    }
+}
+
+/// Derives a valid host name from an arbitrary (pretty) one, the way systemd-hostnamed does:
+/// disallowed characters are dropped, as are leading, trailing and repeated separators, and the
+/// result is truncated to the maximum length of a host name.
+fn derive_host_name(pretty_name: &str) -> String {
+    let host_name_max = net::host_name_max();
+
+    let mut host_name = String::with_capacity(pretty_name.len().min(host_name_max));
+    let (mut after_dot, mut after_hyphen) = (true, true);
+
+    for c in pretty_name.chars() {
+        if host_name.len() >= host_name_max {
+            break;
+        }
+
+        match c {
+            '.' if !(after_dot || after_hyphen) => {
+                host_name.push(c);
+                (after_dot, after_hyphen) = (true, false);
+            }
+
+            '-' if !after_dot => {
+                host_name.push(c);
+                (after_dot, after_hyphen) = (false, true);
+            }
+
+            c if c.is_ascii_alphanumeric() => {
+                host_name.push(c);
+                (after_dot, after_hyphen) = (false, false);
+            }
+
+            _ => {}
+        }
+    }
+
+    if host_name.ends_with(['.', '-']) {
+        host_name.pop();
+    }
+    host_name
+}
+
+#[must_use]
+pub fn uu_app() -> Command {
+    let usage = "hostnamectl [--static|--transient|--pretty] [status]
+hostnamectl [--static|--transient|--pretty] hostname [NAME]
+hostnamectl {icon-name|chassis|deployment|location} [VALUE]";
+
+    let command = Command::new(uucore::util_name())
+        .version(crate_version!())
+        .about("Query or change the system host names")
+        .override_usage(format_usage(usage))
+        .infer_long_args(true)
+        .arg(
+            Arg::new(options::STATIC)
+                .long(options::STATIC)
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("only query or change the static host name"),
+        )
+        .arg(
+            Arg::new(options::TRANSIENT)
+                .long(options::TRANSIENT)
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("only query or change the transient host name"),
+        )
+        .arg(
+            Arg::new(options::PRETTY)
+                .long(options::PRETTY)
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("only query or change the pretty host name"),
+        )
+        .arg(
+            Arg::new(options::ROOT)
+                .long(options::ROOT)
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .global(true)
+                .help("operate on the OS image in DIR instead of the running system"),
+        )
+        .arg(
+            Arg::new(options::VIA_HOSTNAMED)
+                .long(options::VIA_HOSTNAMED)
+                .value_name("WHEN")
+                .value_parser(["always", "auto", "never"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("always")
+                .global(true)
+                .conflicts_with(options::ROOT)
+                .help("go through systemd-hostnamed (default: auto)"),
+        )
+        .subcommand(Command::new(options::STATUS).about("show the current host names"))
+        .subcommand(
+            Command::new(options::HOSTNAME)
+                .about("get or set the host names")
+                .arg(Arg::new(options::NAME).value_parser(value_parser!(String))),
+        );
+
+    PROPERTY_COMMANDS
+        .iter()
+        .fold(command, |command, &(name, key)| {
+            command.subcommand(
+                Command::new(name)
+                    .about(format!("get or set {key} in /etc/machine-info"))
+                    .arg(Arg::new(options::VALUE).value_parser(value_parser!(String))),
+            )
+        })
+}
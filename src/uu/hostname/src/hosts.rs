@@ -0,0 +1,93 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Parsing of the static host table, `/etc/hosts`.
+
+/// The address to which Debian-style systems map their own name.
+pub(crate) const SELF_ADDRESS: &[u8] = b"127.0.1.1";
+
+/// One line of the host table: an address followed by a canonical name and optional aliases.
+pub(crate) struct HostsEntry<'a> {
+    pub(crate) address: &'a [u8],
+    pub(crate) names: Vec<&'a [u8]>,
+}
+
+impl<'a> HostsEntry<'a> {
+    fn parse(line: &'a [u8]) -> Option<Self> {
+        let line = match line.iter().position(|&b| b == b'#') {
+            Some(index) => &line[..index],
+            None => line,
+        };
+
+        let mut fields = line
+            .split(u8::is_ascii_whitespace)
+            .filter(|field| !field.is_empty());
+
+        let address = fields.next()?;
+        let names: Vec<&[u8]> = fields.collect();
+        (!names.is_empty()).then_some(Self { address, names })
+    }
+
+    pub(crate) fn canonical_name(&self) -> &'a [u8] {
+        self.names[0]
+    }
+
+    pub(crate) fn contains(&self, name: &[u8]) -> bool {
+        self.names.iter().any(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Returns the entries of the host table `contents`, in file order.
+pub(crate) fn entries(contents: &[u8]) -> impl Iterator<Item = HostsEntry<'_>> {
+    contents
+        .split(|&b| b == b'\n')
+        .filter_map(HostsEntry::parse)
+}
+
+/// Returns `contents` with `address` mapped to `names` alone.
+///
+/// The first line for `address` is rewritten in place and any other one is removed. If there is
+/// none, a line is added after the last IPv4 loopback entry, or at the end.
+pub(crate) fn set_names(contents: &[u8], address: &[u8], names: &[&[u8]]) -> Vec<u8> {
+    let mut new_line = address.to_vec();
+    new_line.push(b'\t');
+    new_line.extend_from_slice(&names.join(&b' '));
+
+    let contents = contents.strip_suffix(b"\n").unwrap_or(contents);
+    let mut lines: Vec<&[u8]> = if contents.is_empty() {
+        Vec::new()
+    } else {
+        contents.split(|&b| b == b'\n').collect()
+    };
+
+    let is_address = |line: &&[u8]| HostsEntry::parse(line).is_some_and(|e| e.address == address);
+    match lines.iter().position(is_address) {
+        Some(index) => {
+            lines[index] = &new_line;
+            let mut index = index + 1;
+            while index < lines.len() {
+                if is_address(&lines[index]) {
+                    lines.remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+        }
+
+        None => {
+            let index = lines
+                .iter()
+                .rposition(|line| {
+                    HostsEntry::parse(line).is_some_and(|e| e.address.starts_with(b"127."))
+                })
+                .map_or(lines.len(), |index| index + 1);
+            lines.insert(index, &new_line);
+        }
+    }
+
+    let mut contents = lines.join(&b'\n');
+    contents.push(b'\n');
+    contents
+}
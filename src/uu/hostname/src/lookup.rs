@@ -0,0 +1,57 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--lookup`: resolves any name or address the way `-f`, `-i` and `-a` resolve the local host
+//! name, which may differ from what getent(1) reports.
+
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+
+use libc::{AF_UNSPEC, AI_CANONNAME, NI_NAMEREQD, NI_NUMERICHOST, SOCK_DGRAM};
+use uucore::error::UResult;
+
+use crate::errors::HostNameError;
+use crate::escape::Escape;
+use crate::net::{AddressInfo, get_name_info};
+
+/// Prints the canonical name of `name`, then each of its addresses with its PTR name, or `-`
+/// for an address without one. An address is looked up like a name, so its canonical name is
+/// the address itself. Names from the resolver are escaped by `escape`.
+pub(crate) fn run(name: &OsStr, escape: &Escape) -> UResult<()> {
+    let name = CString::new(name.as_bytes()).map_err(|_r| HostNameError::InvalidHostName)?;
+    let address_info = AddressInfo::new(&name, AF_UNSPEC, SOCK_DGRAM, 0, AI_CANONNAME)?;
+
+    let mut addresses: Vec<(String, String)> = Vec::new();
+    for ai in address_info.iter() {
+        let address = get_name_info(ai.ai_addr, ai.ai_addrlen, NI_NUMERICHOST)?;
+        let address = address.to_string_lossy().into_owned();
+        if addresses.iter().any(|(known, _)| *known == address) {
+            continue;
+        }
+
+        let ptr_name = get_name_info(ai.ai_addr, ai.ai_addrlen, NI_NAMEREQD)
+            .map_or_else(|_err| "-".to_owned(), |name| escape.name(name.to_bytes()));
+        addresses.push((address, ptr_name));
+    }
+
+    println!(
+        "canonical name: {}",
+        address_info
+            .canonical_name()
+            .map_or("-".into(), |name| escape.name(name.to_bytes()))
+    );
+
+    let width = addresses
+        .iter()
+        .map(|(address, _)| address.len())
+        .max()
+        .unwrap_or_default();
+    for (address, ptr_name) in addresses {
+        println!("address: {address:width$}  {ptr_name}");
+    }
+
+    escape.warn();
+    Ok(())
+}
@@ -0,0 +1,170 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Access to `/etc/machine-info`, which holds the pretty host name and descriptive metadata
+//! about the machine, as documented in machine-info(5).
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+use crate::env_file::{EnvFile, is_valid_key, quote};
+use crate::errors::HostNameError;
+use crate::sysroot::{MACHINE_INFO_FILE, SysRoot};
+use crate::utils::write_file_atomically;
+
+pub(crate) const PRETTY_HOSTNAME: &str = "PRETTY_HOSTNAME";
+pub(crate) const ICON_NAME: &str = "ICON_NAME";
+pub(crate) const CHASSIS: &str = "CHASSIS";
+pub(crate) const DEPLOYMENT: &str = "DEPLOYMENT";
+pub(crate) const LOCATION: &str = "LOCATION";
+pub(crate) const HARDWARE_VENDOR: &str = "HARDWARE_VENDOR";
+pub(crate) const HARDWARE_MODEL: &str = "HARDWARE_MODEL";
+
+/// The keys describing the machine, which structured output includes along with its names.
+pub(crate) const METADATA_KEYS: [&str; 6] = [
+    CHASSIS,
+    DEPLOYMENT,
+    LOCATION,
+    ICON_NAME,
+    HARDWARE_VENDOR,
+    HARDWARE_MODEL,
+];
+
+const CHASSIS_TYPES: [&str; 10] = [
+    "desktop",
+    "laptop",
+    "convertible",
+    "server",
+    "tablet",
+    "handset",
+    "watch",
+    "embedded",
+    "vm",
+    "container",
+];
+
+#[derive(Default)]
+pub(crate) struct MachineInfo {
+    file: EnvFile,
+    /// The line of the first byte that is not valid UTF-8, if any. Such a file is read with
+    /// replacement characters, but not rewritten, which would corrupt it.
+    invalid_utf8_line: Option<usize>,
+}
+
+impl MachineInfo {
+    pub(crate) fn read(root: &SysRoot) -> UResult<Self> {
+        let path = root.resolve(MACHINE_INFO_FILE)?;
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::default(),
+            Err(err) => {
+                return Err(err).map_err_context(|| format!("cannot read {}", path.quote()));
+            }
+        };
+
+        let invalid_utf8_line = std::str::from_utf8(&contents).err().map(|err| {
+            let valid = &contents[..err.valid_up_to()];
+            valid.iter().filter(|&&b| b == b'\n').count() + 1
+        });
+
+        let file = EnvFile::parse(&String::from_utf8_lossy(&contents)).map_err(|err| {
+            HostNameError::Syntax {
+                path,
+                line: err.line,
+                message: err.message,
+            }
+        })?;
+        Ok(Self {
+            file,
+            invalid_utf8_line,
+        })
+    }
+
+    pub(crate) fn write(&self, root: &SysRoot) -> UResult<()> {
+        let path = root.resolve(MACHINE_INFO_FILE)?;
+        if let Some(line) = self.invalid_utf8_line {
+            return Err(HostNameError::Syntax {
+                path,
+                line,
+                message: "not valid UTF-8, refusing to rewrite the file",
+            }
+            .into());
+        }
+
+        write_file_atomically(&path, self.file.to_string().as_bytes())
+            .map_err_context(|| format!("cannot write {}", path.quote()))
+    }
+
+    /// Returns the machine information of the running system, or none if it cannot be read,
+    /// for reports that must not fail because of it.
+    pub(crate) fn read_or_default() -> Self {
+        Self::read(&SysRoot::default()).unwrap_or_default()
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.file.get(key).filter(|value| !value.is_empty())
+    }
+
+    /// Returns all assignments, in file order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.file.iter()
+    }
+
+    /// Sets `key` to `value`, or removes it if `value` is empty.
+    pub(crate) fn set(&mut self, key: &str, value: &str) -> Result<(), HostNameError> {
+        if !is_valid_value(key, value) {
+            return Err(HostNameError::InvalidMachineInfo(key.to_owned()));
+        }
+
+        self.file.set(key, value);
+        Ok(())
+    }
+}
+
+fn is_valid_value(key: &str, value: &str) -> bool {
+    if !is_valid_key(key) || value.chars().any(char::is_control) {
+        return false;
+    }
+
+    match key {
+        _ if value.is_empty() => true,
+        CHASSIS => CHASSIS_TYPES.contains(&value),
+        DEPLOYMENT => !value.contains(char::is_whitespace),
+        ICON_NAME => value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')),
+        _ => true,
+    }
+}
+
+/// Implements `--machine-info [KEY[=VALUE]]`: prints all properties, prints the value of `KEY`,
+/// or assigns `VALUE` to `KEY`.
+pub(crate) fn run(root: &SysRoot, argument: Option<&String>) -> UResult<()> {
+    let mut machine_info = MachineInfo::read(root)?;
+
+    match argument.map(|argument| argument.split_once('=')) {
+        None => {
+            for (key, value) in machine_info.iter() {
+                println!("{key}={}", quote(value));
+            }
+        }
+
+        Some(Some((key, value))) => {
+            machine_info.set(key, value)?;
+            machine_info.write(root)?;
+        }
+
+        Some(None) => {
+            let key = argument.map(String::as_str).unwrap_or_default();
+            let Some(value) = machine_info.get(key) else {
+                let path = root.resolve(MACHINE_INFO_FILE)?;
+                return Err(HostNameError::MissingKey(path, key.to_owned()).into());
+            };
+            println!("{value}");
+        }
+    }
+
+    Ok(())
+}
@@ -3,13 +3,25 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::ffi::{CStr, CString, c_int};
+use std::ffi::{CStr, CString, c_int, c_uint};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::ptr;
 use std::ptr::NonNull;
+use std::time::Instant;
 
+use uucore::display::Quotable;
+#[cfg(target_os = "linux")]
+use uucore::error::FromIo;
 use uucore::error::UResult;
 
 use crate::errors::{GetNameOrAddrInfoError, HostNameError};
+use crate::utils::verbose;
+
+/// The `--verbose` level from which calls to the C library are traced.
+const TRACE: u8 = 1;
+/// The `--verbose` level from which the buffer sizes of those calls, and the socket types and
+/// protocols of the addresses they return, are traced as well.
+const DETAIL: u8 = 2;
 
 impl crate::net::LibraryGuard {
     pub(crate) fn load() -> std::io::Result<Self> {
@@ -38,27 +50,63 @@ fn max_host_name_size() -> usize {
         .saturating_add(1)
 }
 
+/// Returns the maximum length of a host name, in bytes, not counting the terminating null.
+pub(crate) fn host_name_max() -> usize {
+    const _POSIX_HOST_NAME_MAX: usize = 255;
+
+    usize::try_from(unsafe { libc::sysconf(libc::_SC_HOST_NAME_MAX) })
+        .unwrap_or(_POSIX_HOST_NAME_MAX)
+}
+
 pub(crate) fn host_name() -> std::io::Result<CString> {
     let mut buffer: Vec<u8> = vec![0_u8; max_host_name_size()];
     loop {
         errno::set_errno(errno::Errno(0));
 
+        let start = Instant::now();
         if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } == -1 {
             let err = std::io::Error::last_os_error();
             if err.raw_os_error() != Some(libc::ENAMETOOLONG) {
+                trace_call("gethostname", buffer.len(), start, &err);
                 break Err(err);
             }
             // else an error happened because a bigger buffer is needed.
         } else if let Some(index) = buffer.iter().position(|&b| b == 0_u8) {
+            let size = buffer.len();
             buffer.truncate(index + 1);
-            break Ok(unsafe { CString::from_vec_with_nul_unchecked(buffer) });
+            let host_name = unsafe { CString::from_vec_with_nul_unchecked(buffer) };
+            trace_call(
+                "gethostname",
+                size,
+                start,
+                &host_name.to_string_lossy().quote(),
+            );
+            break Ok(host_name);
         }
         // else truncation happened because a bigger buffer is needed.
 
+        verbose(
+            TRACE,
+            format_args!(
+                "gethostname: {} bytes are not enough; retrying with {}",
+                buffer.len(),
+                buffer.len() + 4096
+            ),
+        );
         buffer.resize_with(buffer.len() + 4096, Default::default);
     }
 }
 
+/// Traces that `function` returned `result` in a buffer of `size` bytes, after starting at
+/// `start`.
+fn trace_call(function: &str, size: usize, start: Instant, result: &dyn std::fmt::Display) {
+    verbose(
+        TRACE,
+        format_args!("{function}: {result} in {:?}", start.elapsed()),
+    );
+    verbose(DETAIL, format_args!("  buffer of {size} bytes"));
+}
+
 pub(crate) fn domain_name() -> UResult<Option<CString>> {
     let mut buffer: Vec<u8> = vec![0_u8; 256];
     loop {
@@ -150,6 +198,22 @@ pub(crate) fn set_host_name(host_name: &CStr) -> UResult<()> {
     }
 }
 
+#[cfg(target_os = "linux")]
+pub(crate) fn set_domain_name(domain_name: &CStr) -> UResult<()> {
+    use std::io::{Error, ErrorKind};
+
+    if unsafe { libc::setdomainname(domain_name.as_ptr(), domain_name.count_bytes()) } != -1 {
+        return Ok(());
+    }
+
+    let err = Error::last_os_error();
+    match err.kind() {
+        ErrorKind::PermissionDenied => Err(Box::new(HostNameError::SetDomainNameDenied)),
+        ErrorKind::InvalidInput => Err(Box::new(HostNameError::HostNameTooLong)),
+        _ => Err(err.into()),
+    }
+}
+
 #[allow(clippy::cast_possible_truncation, clippy::as_conversions)]
 pub(crate) fn get_name_info(
     address: *const libc::sockaddr,
@@ -173,6 +237,19 @@ pub(crate) fn get_name_info(
     let initial_size = max_host_name_size();
 
     let mut buffer: Vec<u8> = vec![0_u8; initial_size];
+    let start = Instant::now();
+    let trace = |result: &dyn std::fmt::Display, size: usize| {
+        verbose(
+            TRACE,
+            format_args!(
+                "getnameinfo({}, flags {}): {result} in {:?}",
+                socket_address(address),
+                name_info_flags(flags),
+                start.elapsed()
+            ),
+        );
+        verbose(DETAIL, format_args!("  buffer of {size} bytes"));
+    };
 
     loop {
         #[cfg(any(
@@ -210,14 +287,29 @@ pub(crate) fn get_name_info(
 
             0 => {
                 if let Some(index) = buffer.iter().position(|&byte| byte == 0_u8) {
+                    let size = buffer.len();
                     buffer.truncate(index + 1);
-                    break Ok(unsafe { CString::from_vec_with_nul_unchecked(buffer) });
+                    let name = unsafe { CString::from_vec_with_nul_unchecked(buffer) };
+                    trace(&name.to_string_lossy().quote(), size);
+                    break Ok(name);
                 }
             }
 
-            _ => break Err(HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(r))),
+            _ => {
+                let err = HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(r));
+                trace(&err, buffer.len());
+                break Err(err);
+            }
         }
 
+        verbose(
+            TRACE,
+            format_args!(
+                "getnameinfo: {} bytes are not enough; retrying with {}",
+                buffer.len(),
+                buffer.len() + 4096
+            ),
+        );
         buffer.resize_with(buffer.len() + 4096, Default::default);
     }
 }
@@ -225,6 +317,10 @@ pub(crate) fn get_name_info(
 #[repr(transparent)]
 pub(crate) struct InterfaceAddresses(NonNull<libc::ifaddrs>);
 
+// The list returned by getifaddrs() is plain heap memory owned by this value, so it can be built
+// on one thread (e.g. inside another network namespace) and then consumed on another.
+unsafe impl Send for InterfaceAddresses {}
+
 impl InterfaceAddresses {
     pub(crate) fn new() -> std::io::Result<Self> {
         use std::io::{Error, ErrorKind};
@@ -269,6 +365,121 @@ impl<'ia> Iterator for InterfaceAddressesIter<'ia> {
     }
 }
 
+/// Returns the address of `interface_address` and its size if it is one that identifies this
+/// host: configured on an interface that is up, not a loopback one, and not link-local.
+#[allow(clippy::cast_possible_truncation, clippy::as_conversions)]
+pub(crate) fn filter_map_interface_addresses(
+    interface_address: &libc::ifaddrs,
+) -> Option<(NonNull<libc::sockaddr>, libc::socklen_t)> {
+    let skip = |reason: std::fmt::Arguments| {
+        let interface = unsafe { CStr::from_ptr(interface_address.ifa_name) };
+        verbose(
+            TRACE,
+            format_args!(
+                "skipping an address of interface {}: {reason}",
+                interface.to_string_lossy().quote()
+            ),
+        );
+        None
+    };
+
+    // Ensure the interface has a configured address.
+    let Some(addr) = NonNull::new(interface_address.ifa_addr) else {
+        return skip(format_args!("no address"));
+    };
+
+    if (interface_address.ifa_flags & (libc::IFF_UP as c_uint)) == 0 {
+        return skip(format_args!("the interface is down"));
+    }
+
+    if (interface_address.ifa_flags & (libc::IFF_LOOPBACK as c_uint)) != 0 {
+        return skip(format_args!("this is the loop back interface"));
+    }
+
+    match c_int::from(unsafe { addr.as_ref() }.sa_family) {
+        libc::AF_INET => Some((addr, size_of::<libc::sockaddr_in>() as libc::socklen_t)),
+
+        libc::AF_INET6 => {
+            let ipv6_addr = unsafe { &addr.cast::<libc::sockaddr_in6>().as_ref().sin6_addr };
+            // Ensure ipv6_addr is not an IPv6 link-local address.
+            if in6_is_addr_linklocal(ipv6_addr) || in6_is_addr_mc_linklocal(ipv6_addr) {
+                return skip(format_args!(
+                    "{} is link-local",
+                    Ipv6Addr::from(ipv6_addr.s6_addr)
+                ));
+            }
+            Some((addr, size_of::<libc::sockaddr_in6>() as libc::socklen_t))
+        }
+
+        // Link-layer entries and other families do not hold IP addresses, so they are left out
+        // silently.
+        _ => None,
+    }
+}
+
+/// Returns the hardware (MAC) address of `ifa` if it is a link-layer entry, unless that address
+/// is all zeros.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn hardware_address(ifa: &libc::ifaddrs) -> Option<&[u8]> {
+    let addr = unsafe { ifa.ifa_addr.as_ref()? };
+    if c_int::from(addr.sa_family) != libc::AF_PACKET {
+        return None;
+    }
+
+    let addr = unsafe { &*ifa.ifa_addr.cast::<libc::sockaddr_ll>() };
+    let len = usize::from(addr.sll_halen).min(addr.sll_addr.len());
+    let address = &addr.sll_addr[..len];
+    address.iter().any(|&byte| byte != 0).then_some(address)
+}
+
+/// A network namespace, given either as a name under `/run/netns` (as created by `ip netns add`)
+/// or as a path such as `/proc/PID/ns/net`.
+#[cfg(target_os = "linux")]
+pub(crate) struct NetworkNamespace {
+    path: std::path::PathBuf,
+    file: std::fs::File,
+}
+
+#[cfg(target_os = "linux")]
+impl NetworkNamespace {
+    pub(crate) fn open(name: &std::ffi::OsStr) -> UResult<Self> {
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::{Path, PathBuf};
+
+        let path = if name.as_bytes().contains(&b'/') {
+            PathBuf::from(name)
+        } else {
+            Path::new("/run/netns").join(name)
+        };
+
+        match std::fs::File::open(&path) {
+            Ok(file) => Ok(Self { path, file }),
+            Err(err) => Err(err)
+                .map_err_context(|| format!("cannot open network namespace {}", path.quote())),
+        }
+    }
+
+    /// Runs `f` on a dedicated thread that first joins this network namespace. The namespace of
+    /// the calling thread is left untouched.
+    pub(crate) fn run<T: Send>(&self, f: impl FnOnce() -> T + Send) -> UResult<T> {
+        use std::os::fd::AsRawFd;
+
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    if unsafe { libc::setns(self.file.as_raw_fd(), libc::CLONE_NEWNET) } == -1 {
+                        return Err(std::io::Error::last_os_error()).map_err_context(|| {
+                            format!("cannot enter network namespace {}", self.path.quote())
+                        });
+                    }
+                    Ok(f())
+                })
+                .join()
+                .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+        })
+    }
+}
+
 #[repr(transparent)]
 pub(crate) struct AddressInfo(NonNull<libc::addrinfo>);
 
@@ -279,7 +490,7 @@ impl AddressInfo {
         hint_socktype: c_int,
         hint_protocol: c_int,
         hint_flags: c_int,
-    ) -> UResult<Self> {
+    ) -> Result<Self, HostNameError> {
         let mut c_hints: libc::addrinfo = unsafe { std::mem::zeroed() };
         c_hints.ai_family = hint_family;
         c_hints.ai_socktype = hint_socktype;
@@ -288,22 +499,84 @@ impl AddressInfo {
 
         let mut ptr: *mut libc::addrinfo = ptr::null_mut();
 
+        verbose(
+            TRACE,
+            format_args!(
+                "getaddrinfo({}, hints: family {}, socket type {}, protocol {hint_protocol}, \
+                 flags {})",
+                host_name.to_string_lossy().quote(),
+                family_name(hint_family),
+                socket_type_name(hint_socktype),
+                address_info_flags(hint_flags)
+            ),
+        );
+        let start = Instant::now();
         let r = unsafe { libc::getaddrinfo(host_name.as_ptr(), ptr::null(), &c_hints, &mut ptr) };
-        if r == 0 {
-            NonNull::new(ptr)
-                .map(Self)
-                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData).into())
-        } else {
-            Err(Box::new(HostNameError::GetNameOrAddrInfo(
-                GetNameOrAddrInfoError(r),
-            )))
+        if r != 0 {
+            let err = HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(r));
+            verbose(
+                TRACE,
+                format_args!("getaddrinfo: {err} in {:?}", start.elapsed()),
+            );
+            return Err(err);
+        }
+
+        let address_info = NonNull::new(ptr)
+            .map(Self)
+            .ok_or(HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(
+                libc::EAI_FAIL,
+            )))?;
+        verbose(
+            TRACE,
+            format_args!(
+                "getaddrinfo: {} in {:?}",
+                match address_info.iter().count() {
+                    1 => "1 entry".to_owned(),
+                    count => format!("{count} entries"),
+                },
+                start.elapsed()
+            ),
+        );
+        for ai in address_info.iter() {
+            verbose(
+                TRACE,
+                format_args!(
+                    "  {} address {}",
+                    family_name(ai.ai_family),
+                    socket_address(ai.ai_addr)
+                ),
+            );
+            verbose(
+                DETAIL,
+                format_args!(
+                    "    socket type {}, protocol {}",
+                    socket_type_name(ai.ai_socktype),
+                    ai.ai_protocol
+                ),
+            );
         }
+        if let Some(canonical_name) = address_info.canonical_name() {
+            verbose(
+                TRACE,
+                format_args!(
+                    "  canonical name {}",
+                    canonical_name.to_string_lossy().quote()
+                ),
+            );
+        }
+        Ok(address_info)
     }
 
     pub(crate) fn first(&self) -> &libc::addrinfo {
         unsafe { self.0.as_ref() }
     }
 
+    /// Returns the canonical name, which is only set when `AI_CANONNAME` was given.
+    pub(crate) fn canonical_name(&self) -> Option<&CStr> {
+        let canonical_name = self.first().ai_canonname;
+        (!canonical_name.is_null()).then(|| unsafe { CStr::from_ptr(canonical_name) })
+    }
+
     pub(crate) fn iter(&self) -> AddressInfoIter<'_> {
         AddressInfoIter {
             _ia: self,
@@ -332,3 +605,83 @@ impl<'ia> Iterator for AddressInfoIter<'ia> {
         Some(element)
     }
 }
+
+fn family_name(family: c_int) -> String {
+    match family {
+        libc::AF_UNSPEC => "AF_UNSPEC".into(),
+        libc::AF_INET => "AF_INET".into(),
+        libc::AF_INET6 => "AF_INET6".into(),
+        family => family.to_string(),
+    }
+}
+
+fn socket_type_name(socket_type: c_int) -> String {
+    match socket_type {
+        0 => "any".into(),
+        libc::SOCK_STREAM => "SOCK_STREAM".into(),
+        libc::SOCK_DGRAM => "SOCK_DGRAM".into(),
+        libc::SOCK_RAW => "SOCK_RAW".into(),
+        socket_type => socket_type.to_string(),
+    }
+}
+
+fn address_info_flags(flags: c_int) -> String {
+    flag_names(
+        flags,
+        &[
+            (libc::AI_PASSIVE, "AI_PASSIVE"),
+            (libc::AI_CANONNAME, "AI_CANONNAME"),
+            (libc::AI_NUMERICHOST, "AI_NUMERICHOST"),
+            (libc::AI_NUMERICSERV, "AI_NUMERICSERV"),
+        ],
+    )
+}
+
+fn name_info_flags(flags: c_int) -> String {
+    flag_names(
+        flags,
+        &[
+            (libc::NI_NUMERICHOST, "NI_NUMERICHOST"),
+            (libc::NI_NUMERICSERV, "NI_NUMERICSERV"),
+            (libc::NI_NOFQDN, "NI_NOFQDN"),
+            (libc::NI_NAMEREQD, "NI_NAMEREQD"),
+            (libc::NI_DGRAM, "NI_DGRAM"),
+        ],
+    )
+}
+
+/// Returns the names of the bits of `flags` joined by `|`, followed by the value of the bits
+/// without a name, or `0` if no bit is set.
+fn flag_names(flags: c_int, names: &[(c_int, &str)]) -> String {
+    let mut parts: Vec<String> = names
+        .iter()
+        .filter(|&&(flag, _)| flags & flag != 0)
+        .map(|&(_, name)| name.into())
+        .collect();
+    let unnamed = names.iter().fold(flags, |rest, &(flag, _)| rest & !flag);
+    if unnamed != 0 || parts.is_empty() {
+        parts.push(format!("{unnamed:#x}"));
+    }
+    parts.join("|")
+}
+
+/// Returns the IP address of `address` in text, without calling getnameinfo(3), which would be
+/// traced in turn.
+fn socket_address(address: *const libc::sockaddr) -> String {
+    let Some(sockaddr) = (unsafe { address.as_ref() }) else {
+        return "(none)".into();
+    };
+
+    let ip_address = match c_int::from(sockaddr.sa_family) {
+        libc::AF_INET => {
+            let address = unsafe { &*address.cast::<libc::sockaddr_in>() };
+            IpAddr::V4(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)))
+        }
+        libc::AF_INET6 => {
+            let address = unsafe { &*address.cast::<libc::sockaddr_in6>() };
+            IpAddr::V6(Ipv6Addr::from(address.sin6_addr.s6_addr))
+        }
+        family => return format!("(address family {family})"),
+    };
+    ip_address.to_string()
+}
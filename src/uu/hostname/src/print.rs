@@ -3,23 +3,155 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(not(target_family = "windows"))]
+mod image;
 #[cfg(not(target_family = "windows"))]
 mod unix;
 #[cfg(target_family = "windows")]
 mod windows;
 
+use std::io::Write;
+
 use uucore::error::UResult;
+use uucore::show;
+
+use crate::escape::Escape;
+
+#[cfg(not(target_family = "windows"))]
+pub(crate) use unix::HostLookup;
 
 pub(crate) trait PrintHostName {
     fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()>;
 }
 
-pub(crate) struct DefaultHostName;
-pub(crate) struct AliasHostName;
-pub(crate) struct DomainHostName;
-pub(crate) struct FqdnHostName;
-pub(crate) struct AllFqdnHostName;
-pub(crate) struct IpAddressHostName;
-pub(crate) struct AllIpAddressesHostName;
+pub(crate) struct DefaultHostName {
+    /// Ask systemd-hostnamed for the host name, instead of the kernel.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) hostnamed: Option<crate::dbus::Hostnamed>,
+}
+
+pub(crate) struct AliasHostName {
+    /// The resolution of the host name, shared with the other printers.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) lookup: std::rc::Rc<HostLookup>,
+}
+
+pub(crate) struct DomainHostName {
+    /// The resolution of the host name, shared with the other printers.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) lookup: std::rc::Rc<HostLookup>,
+    /// Use the local domain of the resolver when the canonical name has no domain.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) fallback: bool,
+}
+
+pub(crate) struct FqdnHostName {
+    /// The resolution of the host name, shared with the other printers.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) lookup: std::rc::Rc<HostLookup>,
+    /// Append the local domain of the resolver to the host name when it does not resolve to a
+    /// fully qualified name.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) fallback: bool,
+}
+
+pub(crate) struct AllFqdnHostName {
+    /// Enumerate interface addresses inside this network namespace.
+    #[cfg(target_os = "linux")]
+    pub(crate) netns: Option<crate::net::NetworkNamespace>,
+    /// Also perform reverse resolution inside `netns`, instead of in the caller's namespace.
+    #[cfg(target_os = "linux")]
+    pub(crate) netns_resolve: bool,
+}
+
+pub(crate) struct IpAddressHostName {
+    /// The resolution of the host name, shared with the other printers.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) lookup: std::rc::Rc<HostLookup>,
+    /// Print the interface addresses, as `-I` does, when the host name does not resolve.
+    #[cfg(not(target_family = "windows"))]
+    pub(crate) fallback: bool,
+}
+
+pub(crate) struct AllIpAddressesHostName {
+    /// Enumerate interface addresses inside this network namespace.
+    #[cfg(target_os = "linux")]
+    pub(crate) netns: Option<crate::net::NetworkNamespace>,
+}
+
 pub(crate) struct ShortHostName;
 pub(crate) struct NisHostName;
+
+/// Prints names from the static configuration of a system, rather than from the running kernel
+/// and resolver.
+#[cfg(not(target_family = "windows"))]
+pub(crate) struct StaticHostName {
+    pub(crate) root: crate::sysroot::SysRoot,
+    pub(crate) query: StaticQuery,
+}
+
+#[cfg(not(target_family = "windows"))]
+pub(crate) enum StaticQuery {
+    HostName,
+    Alias,
+    Domain,
+    Fqdn,
+    IpAddress,
+    Short,
+    Nis,
+}
+
+/// Prints the answers to `queries`, which are paired with their labels, escaped by `escape`.
+/// A single answer is printed alone; several are printed one per line, after their label unless
+/// `labels` is false.
+///
+/// When there are several queries, one that fails is reported but leaves an empty answer, so
+/// that the others are still printed, in order.
+pub(crate) fn print_all(
+    queries: &[(&str, Box<dyn PrintHostName>)],
+    labels: bool,
+    escape: &Escape,
+) -> UResult<()> {
+    let mut out = std::io::stdout().lock();
+
+    if let [(_, query)] = queries {
+        if escape.is_never() {
+            return query.print_host_name(&mut out);
+        }
+
+        let mut answer = Vec::new();
+        let result = query.print_host_name(&mut answer);
+        let mut escaped = Vec::new();
+        for line in answer.split_inclusive(|&b| b == b'\n') {
+            let text = line.strip_suffix(b"\n");
+            escape.push(&mut escaped, text.unwrap_or(line));
+            if text.is_some() {
+                escaped.push(b'\n');
+            }
+        }
+        out.write_all(&escaped)?;
+        escape.warn();
+        return result;
+    }
+
+    for (label, query) in queries {
+        let mut answer = Vec::new();
+        if let Err(err) = query.print_host_name(&mut answer) {
+            show!(err);
+            answer.clear();
+        }
+
+        let answer = answer.strip_suffix(b"\n").unwrap_or(&answer);
+        let mut line = Vec::new();
+        if labels {
+            line.extend_from_slice(label.as_bytes());
+            line.extend_from_slice(if answer.is_empty() { b":" } else { b": " });
+        }
+        escape.push(&mut line, answer);
+        line.push(b'\n');
+        out.write_all(&line)?;
+    }
+
+    escape.warn();
+    Ok(())
+}
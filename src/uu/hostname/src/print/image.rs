@@ -0,0 +1,102 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use uucore::error::UResult;
+
+use crate::errors::{GetNameOrAddrInfoError, HostNameError};
+use crate::hosts;
+use crate::print::{PrintHostName, StaticHostName, StaticQuery};
+use crate::sysroot::{DEFAULT_DOMAIN_FILE, HOSTNAME_FILE, HOSTS_FILE};
+
+const NONAME: HostNameError =
+    HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(libc::EAI_NONAME));
+
+impl StaticHostName {
+    fn host_name(&self) -> UResult<Vec<u8>> {
+        self.root
+            .read_name(HOSTNAME_FILE)?
+            .ok_or_else(|| HostNameError::NoStaticHostName.into())
+    }
+
+    fn hosts(&self) -> UResult<Vec<u8>> {
+        match std::fs::read(self.root.resolve(HOSTS_FILE)?) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::default()),
+            result => result.map_err(From::from),
+        }
+    }
+}
+
+impl PrintHostName for StaticHostName {
+    fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
+        let mut separator: &[u8] = &[];
+        let mut write_name = |name: &[u8]| -> std::io::Result<()> {
+            out.write_all(separator)?;
+            separator = b" ";
+            out.write_all(name)
+        };
+
+        match self.query {
+            StaticQuery::HostName => write_name(&self.host_name()?)?,
+
+            StaticQuery::Short => {
+                let host_name = self.host_name()?;
+                write_name(host_name.split(|&b| b == b'.').next().unwrap_or_default())?;
+            }
+
+            StaticQuery::Nis => {
+                let domain_name = self
+                    .root
+                    .read_name(DEFAULT_DOMAIN_FILE)?
+                    .ok_or(HostNameError::NoLocalDomainName)?;
+                write_name(&domain_name)?;
+            }
+
+            StaticQuery::Alias => {
+                let host_name = self.host_name()?;
+                let hosts = self.hosts()?;
+                let mut names: Vec<&[u8]> = vec![&host_name];
+                for entry in hosts::entries(&hosts).filter(|e| e.contains(&host_name)) {
+                    for name in &entry.names {
+                        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                            names.push(name);
+                        }
+                    }
+                }
+                names.into_iter().try_for_each(&mut write_name)?;
+            }
+
+            StaticQuery::Domain | StaticQuery::Fqdn => {
+                let host_name = self.host_name()?;
+                let hosts = self.hosts()?;
+                let entry = hosts::entries(&hosts)
+                    .find(|e| e.contains(&host_name))
+                    .ok_or(NONAME)?;
+                let canonical_name = entry.canonical_name();
+
+                if matches!(self.query, StaticQuery::Fqdn) {
+                    write_name(canonical_name)?;
+                } else if let Some(domain_name) = canonical_name.splitn(2, |&b| b == b'.').nth(1) {
+                    write_name(domain_name)?;
+                } else {
+                    return Ok(()); // Canonical name contains zero dots.
+                }
+            }
+
+            StaticQuery::IpAddress => {
+                let host_name = self.host_name()?;
+                let hosts = self.hosts()?;
+                let mut entries = hosts::entries(&hosts)
+                    .filter(|e| e.contains(&host_name))
+                    .peekable();
+                if entries.peek().is_none() {
+                    return Err(NONAME.into());
+                }
+                entries.try_for_each(|e| write_name(e.address))?;
+            }
+        }
+
+        out.write_all(b"\n").map_err(From::from)
+    }
+}
@@ -3,27 +3,48 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::ffi::{CStr, c_int, c_uint};
-use std::ptr::NonNull;
+use std::cell::OnceCell;
+use std::ffi::{CStr, CString};
 
-use libc::{
-    AF_UNSPEC, AI_CANONNAME, IFF_LOOPBACK, IFF_UP, NI_NAMEREQD, NI_NUMERICHOST, SOCK_DGRAM,
-    sockaddr, sockaddr_in, sockaddr_in6, socklen_t,
-};
+use libc::{AF_UNSPEC, AI_CANONNAME, NI_NAMEREQD, NI_NUMERICHOST, SOCK_DGRAM};
+use uucore::display::Quotable;
 use uucore::error::UResult;
 
 use crate::errors::{GetNameOrAddrInfoError, HostNameError};
 use crate::net::{
-    AddressInfo, InterfaceAddresses, domain_name, get_name_info, host_name, in6_is_addr_linklocal,
-    in6_is_addr_mc_linklocal, short_host_name,
+    AddressInfo, InterfaceAddresses, domain_name, filter_map_interface_addresses, get_name_info,
+    host_name, short_host_name,
 };
 use crate::print::{
     AliasHostName, AllFqdnHostName, AllIpAddressesHostName, DefaultHostName, DomainHostName,
     FqdnHostName, IpAddressHostName, NisHostName, PrintHostName, ShortHostName,
 };
+use crate::resolv_conf::local_domain;
+use crate::utils::verbose;
+
+/// The resolution of the host name, made at most once and shared by the printers of an
+/// invocation, so that `-a -d -f -i` make a single getaddrinfo(3) call.
+#[derive(Default)]
+pub(crate) struct HostLookup(OnceCell<Result<AddressInfo, HostNameError>>);
+
+impl HostLookup {
+    /// Resolves `host_name`, which is the same on every call, along with its canonical name.
+    fn resolve(&self, host_name: &CStr) -> UResult<&AddressInfo> {
+        self.0
+            .get_or_init(|| AddressInfo::new(host_name, AF_UNSPEC, SOCK_DGRAM, 0, AI_CANONNAME))
+            .as_ref()
+            .map_err(|err| err.clone().into())
+    }
+}
 
 impl PrintHostName for DefaultHostName {
     fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
+        if let Some(hostnamed) = &self.hostnamed {
+            let host_name = hostnamed.get("Hostname")?;
+            out.write_all(host_name.as_bytes())?;
+            return out.write_all(b"\n").map_err(From::from);
+        }
+
         let host_name = host_name()?;
         out.write_all(host_name.as_bytes())?;
         out.write_all(b"\n").map_err(From::from)
@@ -40,7 +61,7 @@ impl PrintHostName for AliasHostName {
         // think of, that is still portable.
 
         let host_name = host_name()?;
-        let address_info = AddressInfo::new(&host_name, AF_UNSPEC, SOCK_DGRAM, 0, 0)?;
+        let address_info = self.lookup.resolve(&host_name)?;
 
         out.write_all(host_name.as_bytes())?;
 
@@ -56,85 +77,126 @@ impl PrintHostName for AliasHostName {
     }
 }
 
-impl PrintHostName for DomainHostName {
-    fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
-        let address_info = AddressInfo::new(&host_name()?, AF_UNSPEC, SOCK_DGRAM, 0, AI_CANONNAME)?;
+/// Resolves the host name for `-d` and `-f`, returning it along with its canonical name.
+///
+/// With `fallback`, a host name that does not resolve has no canonical name, instead of being an
+/// error.
+fn canonical_host_name(lookup: &HostLookup, fallback: bool) -> UResult<(CString, Option<CString>)> {
+    let host_name = host_name()?;
+    match lookup.resolve(&host_name) {
+        Ok(address_info) => {
+            let canonical_name = address_info.canonical_name().map(CStr::to_owned);
+            Ok((host_name, canonical_name))
+        }
+        Err(err) if fallback => {
+            verbose(
+                1,
+                format_args!(
+                    "cannot resolve {}: {err}",
+                    host_name.to_string_lossy().quote()
+                ),
+            );
+            Ok((host_name, None))
+        }
+        Err(err) => Err(err),
+    }
+}
 
-        let canonical_name = address_info.first().ai_canonname;
-        if canonical_name.is_null() {
-            return Ok(()); // No canonical name set.
-        };
+/// Returns what follows the first dot of `name`, if it has one.
+fn domain_of(name: &[u8]) -> Option<&[u8]> {
+    name.splitn(2, |&byte| byte == b'.').nth(1)
+}
 
-        let Some(domain_name) = unsafe { CStr::from_ptr(canonical_name) }
-            .to_bytes()
-            .splitn(2, |&byte| byte == b'.')
-            .nth(1)
-        else {
-            return Ok(()); // Canonical name contains zero dots.
-        };
+impl PrintHostName for DomainHostName {
+    fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
+        let (_, canonical_name) = canonical_host_name(&self.lookup, self.fallback)?;
+
+        if let Some(domain_name) = canonical_name
+            .as_deref()
+            .and_then(|name| domain_of(name.to_bytes()))
+        {
+            if self.fallback {
+                verbose(1, format_args!("using the domain of the canonical name"));
+            }
+            out.write_all(domain_name)?;
+        } else if self.fallback
+            && let Some((domain_name, source)) = local_domain()
+        {
+            verbose(1, format_args!("using the local domain from {source}"));
+            out.write_all(domain_name.as_bytes())?;
+        } else {
+            return Ok(()); // No canonical name set, or it contains zero dots.
+        }
 
-        out.write_all(domain_name)?;
         out.write_all(b"\n").map_err(From::from)
     }
 }
 
 impl PrintHostName for FqdnHostName {
     fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
-        let address_info = AddressInfo::new(&host_name()?, AF_UNSPEC, SOCK_DGRAM, 0, AI_CANONNAME)?;
-
-        let canonical_name = address_info.first().ai_canonname;
-        if canonical_name.is_null() {
-            return Ok(()); // No canonical name set.
-        };
+        let (host_name, canonical_name) = canonical_host_name(&self.lookup, self.fallback)?;
+
+        match canonical_name {
+            Some(canonical_name) if !self.fallback => {
+                out.write_all(canonical_name.to_bytes())?;
+            }
+            None if !self.fallback => return Ok(()), // No canonical name set.
+
+            Some(canonical_name) if canonical_name.to_bytes().contains(&b'.') => {
+                verbose(1, format_args!("using the canonical name"));
+                out.write_all(canonical_name.to_bytes())?;
+            }
+            _ if host_name.to_bytes().contains(&b'.') => {
+                verbose(1, format_args!("using the host name, which has a domain"));
+                out.write_all(host_name.to_bytes())?;
+            }
+            _ => {
+                out.write_all(host_name.to_bytes())?;
+                if let Some((domain_name, source)) = local_domain() {
+                    verbose(1, format_args!("appending the local domain from {source}"));
+                    out.write_all(b".")?;
+                    out.write_all(domain_name.as_bytes())?;
+                } else {
+                    verbose(1, format_args!("no local domain; using the host name"));
+                }
+            }
+        }
 
-        out.write_all(unsafe { CStr::from_ptr(canonical_name) }.to_bytes())?;
         out.write_all(b"\n").map_err(From::from)
     }
 }
 
-#[allow(clippy::cast_possible_truncation, clippy::as_conversions)]
-fn filter_map_interface_addresses(
-    interface_address: &libc::ifaddrs,
-) -> Option<(NonNull<sockaddr>, socklen_t)> {
-    // Ensure the interface has a configured address.
-    let addr = NonNull::new(interface_address.ifa_addr)?;
-
-    if (interface_address.ifa_flags & (IFF_UP as c_uint)) == 0 {
-        return None; // Interface is down.
-    }
-
-    if (interface_address.ifa_flags & (IFF_LOOPBACK as c_uint)) != 0 {
-        return None; // This is the loop back interface.
-    }
-
-    match c_int::from(unsafe { addr.as_ref() }.sa_family) {
-        libc::AF_INET => Some((addr, size_of::<sockaddr_in>() as socklen_t)),
-
-        libc::AF_INET6 => {
-            let ipv6_addr = unsafe { &addr.cast::<sockaddr_in6>().as_ref().sin6_addr };
-            // Ensure ipv6_addr is not an IPv6 link-local address.
-            (!in6_is_addr_linklocal(ipv6_addr) && !in6_is_addr_mc_linklocal(ipv6_addr))
-                .then_some((addr, size_of::<sockaddr_in6>() as socklen_t))
-        }
-
-        _ => None, // Unsupported address family.
+impl AllFqdnHostName {
+    fn names(interface_addresses: &InterfaceAddresses) -> Vec<CString> {
+        interface_addresses
+            .iter()
+            .filter_map(filter_map_interface_addresses)
+            // Skip addresses whose translation fails.
+            .filter_map(|(addr, size)| get_name_info(addr.as_ptr(), size, NI_NAMEREQD).ok())
+            .collect()
     }
 }
 
 impl PrintHostName for AllFqdnHostName {
     fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
-        let mut separator: &[u8] = &[];
+        #[cfg(target_os = "linux")]
+        let names = match &self.netns {
+            Some(netns) if self.netns_resolve => {
+                netns.run(|| InterfaceAddresses::new().map(|ia| Self::names(&ia)))??
+            }
+            Some(netns) => Self::names(&netns.run(InterfaceAddresses::new)??),
+            None => Self::names(&InterfaceAddresses::new()?),
+        };
 
-        InterfaceAddresses::new()?
-            .iter()
-            .filter_map(filter_map_interface_addresses)
-            // Skip addresses whose translation fails.
-            .filter_map(|(addr, size)| get_name_info(addr.as_ptr(), size, NI_NAMEREQD).ok())
-            .try_for_each(|name| {
-                out.write_all(separator)?;
-                separator = b" ";
-                out.write_all(name.as_bytes())
-            })?;
+        #[cfg(not(target_os = "linux"))]
+        let names = Self::names(&InterfaceAddresses::new()?);
+
+        let mut separator: &[u8] = &[];
+        for name in names {
+            out.write_all(separator)?;
+            separator = b" ";
+            out.write_all(name.as_bytes())?;
+        }
 
         out.write_all(b"\n").map_err(From::from)
     }
@@ -144,7 +206,31 @@ impl PrintHostName for IpAddressHostName {
     fn print_host_name(&self, out: &mut dyn std::io::Write) -> UResult<()> {
         let mut separator: &[u8] = &[];
 
-        AddressInfo::new(&host_name()?, AF_UNSPEC, SOCK_DGRAM, 0, 0)?
+        let host_name = host_name()?;
+        let address_info = match self.lookup.resolve(&host_name) {
+            Ok(address_info) => address_info,
+            Err(err) if self.fallback => {
+                verbose(
+                    1,
+                    format_args!(
+                        "cannot resolve {}: {err}; using the interface addresses",
+                        host_name.to_string_lossy().quote()
+                    ),
+                );
+                let all_addresses = AllIpAddressesHostName {
+                    #[cfg(target_os = "linux")]
+                    netns: None,
+                };
+                return all_addresses.print_host_name(out);
+            }
+            Err(err) => return Err(err),
+        };
+
+        if self.fallback {
+            verbose(1, format_args!("using the resolved addresses"));
+        }
+
+        address_info
             .iter()
             .map(|ai| get_name_info(ai.ai_addr, ai.ai_addrlen, NI_NUMERICHOST))
             .try_for_each(|name| -> UResult<()> {
@@ -162,9 +248,18 @@ impl PrintHostName for AllIpAddressesHostName {
         const NONAME: HostNameError =
             HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(libc::EAI_NONAME));
 
+        #[cfg(target_os = "linux")]
+        let interface_addresses = match &self.netns {
+            Some(netns) => netns.run(InterfaceAddresses::new)??,
+            None => InterfaceAddresses::new()?,
+        };
+
+        #[cfg(not(target_os = "linux"))]
+        let interface_addresses = InterfaceAddresses::new()?;
+
         let mut separator: &[u8] = &[];
 
-        InterfaceAddresses::new()?
+        interface_addresses
             .iter()
             .filter_map(filter_map_interface_addresses)
             .map(|(addr, addr_size)| get_name_info(addr.as_ptr(), addr_size, NI_NUMERICHOST))
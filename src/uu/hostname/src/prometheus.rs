@@ -0,0 +1,173 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! `--prometheus`: the names and addresses of the host as metrics in the Prometheus text
+//! format, for the textfile collector of node_exporter.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::rc::Rc;
+use std::time::Instant;
+
+use libc::NI_NUMERICHOST;
+use uucore::error::UResult;
+
+use crate::errors::{GetNameOrAddrInfoError, HostNameError};
+use crate::format::{evaluate, printer};
+use crate::machine_info::{METADATA_KEYS, MachineInfo};
+use crate::net::{InterfaceAddresses, filter_map_interface_addresses, get_name_info};
+use crate::print::{AllFqdnHostName, HostLookup, PrintHostName};
+
+/// Prints the metrics. A lookup that fails is reported by its metrics rather than as an error,
+/// so that a collector still gets the others.
+pub(crate) fn run(fallback: bool) -> UResult<()> {
+    let fqdn_lookup = Rc::new(HostLookup::default());
+    let value =
+        |placeholder| evaluate(&*printer(placeholder, &fqdn_lookup, fallback)).unwrap_or_default();
+
+    // Each lookup is timed on its own, without the answers the others have cached.
+    let lookups: [(&str, Box<dyn PrintHostName>); 6] = [
+        ("hostname", printer('h', &fqdn_lookup, fallback)),
+        ("fqdn", printer('f', &fqdn_lookup, fallback)),
+        (
+            "ip_address",
+            printer('i', &Rc::new(HostLookup::default()), fallback),
+        ),
+        ("all_ip_addresses", printer('I', &fqdn_lookup, fallback)),
+        (
+            "all_fqdns",
+            Box::new(AllFqdnHostName {
+                #[cfg(target_os = "linux")]
+                netns: None,
+                #[cfg(target_os = "linux")]
+                netns_resolve: false,
+            }),
+        ),
+        ("nis_domain", printer('y', &fqdn_lookup, fallback)),
+    ];
+    let mut durations = String::new();
+    let mut successes = String::new();
+    for (lookup, printer) in lookups {
+        let start = Instant::now();
+        let succeeded = evaluate(&*printer).is_ok();
+        let duration = start.elapsed();
+
+        let _ = writeln!(
+            durations,
+            "hostname_lookup_duration_seconds{{lookup=\"{lookup}\"}} {:.6}",
+            duration.as_secs_f64()
+        );
+        let _ = writeln!(
+            successes,
+            "hostname_lookup_success{{lookup=\"{lookup}\"}} {}",
+            u8::from(succeeded)
+        );
+    }
+
+    let machine_info = MachineInfo::read_or_default();
+    let metadata: String = METADATA_KEYS
+        .into_iter()
+        .map(|key| {
+            let value = machine_info.get(key).unwrap_or_default();
+            format!(
+                ",{}={}",
+                key.to_ascii_lowercase(),
+                label_value(value.as_bytes())
+            )
+        })
+        .collect();
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "# HELP hostname_info Names of the host, and the description of the machine from \
+         /etc/machine-info, as labels.\n\
+         # TYPE hostname_info gauge\n\
+         hostname_info{{hostname={},short={},fqdn={},domain={},nis_domain={}{metadata}}} 1",
+        label_value(&value('h')),
+        label_value(&value('s')),
+        label_value(&value('f')),
+        label_value(&value('d')),
+        label_value(&value('y')),
+    );
+
+    output.push_str(
+        "# HELP hostname_interface_addresses Number of addresses that identify the host, as \
+         printed by -I, by interface and family.\n\
+         # TYPE hostname_interface_addresses gauge\n",
+    );
+    for ((interface, family), count) in interface_address_counts()? {
+        let _ = writeln!(
+            output,
+            "hostname_interface_addresses{{interface={},family=\"{family}\"}} {count}",
+            label_value(interface.as_bytes())
+        );
+    }
+
+    output.push_str(
+        "# HELP hostname_lookup_duration_seconds Time taken by each lookup of the host names.\n\
+         # TYPE hostname_lookup_duration_seconds gauge\n",
+    );
+    output.push_str(&durations);
+    output.push_str(
+        "# HELP hostname_lookup_success Whether each lookup of the host names succeeded.\n\
+         # TYPE hostname_lookup_success gauge\n",
+    );
+    output.push_str(&successes);
+
+    std::io::stdout()
+        .write_all(output.as_bytes())
+        .map_err(From::from)
+}
+
+/// Counts the addresses that `-I` prints, by interface and family: those that the same filter
+/// lets through, and that have a numeric form.
+fn interface_address_counts() -> UResult<BTreeMap<(String, &'static str), usize>> {
+    const NONAME: HostNameError =
+        HostNameError::GetNameOrAddrInfo(GetNameOrAddrInfoError(libc::EAI_NONAME));
+
+    let interface_addresses = InterfaceAddresses::new()?;
+    let mut counts = BTreeMap::new();
+
+    for ifa in interface_addresses.iter() {
+        let Some((addr, size)) = filter_map_interface_addresses(ifa) else {
+            continue;
+        };
+        match get_name_info(addr.as_ptr(), size, NI_NUMERICHOST) {
+            Ok(_) => {}
+            Err(err) if err == NONAME => continue,
+            Err(err) => return Err(err.into()),
+        }
+        let family = if i32::from(unsafe { addr.as_ref() }.sa_family) == libc::AF_INET {
+            "ipv4"
+        } else {
+            "ipv6"
+        };
+        let interface = unsafe { CStr::from_ptr(ifa.ifa_name) };
+        *counts
+            .entry((interface.to_string_lossy().into_owned(), family))
+            .or_default() += 1;
+    }
+
+    Ok(counts)
+}
+
+/// Returns `value` as a quoted label value, in which `\`, `"` and line feeds are escaped. The
+/// text format is UTF-8, so other bytes become U+FFFD.
+fn label_value(value: &[u8]) -> String {
+    let mut quoted = String::from("\"");
+    for c in String::from_utf8_lossy(value).chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,37 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The local domain of the resolver, which it appends to names without a dot.
+
+use crate::sysroot::RESOLV_CONF_FILE;
+
+/// Returns the local domain and where it comes from: the first domain of `LOCALDOMAIN` if that
+/// is set, like the resolver does, or else of the last `domain` or `search` line of
+/// resolv.conf(5).
+pub(crate) fn local_domain() -> Option<(String, &'static str)> {
+    if let Some(value) = std::env::var_os("LOCALDOMAIN") {
+        return first_domain(value.to_string_lossy().split_ascii_whitespace())
+            .map(|domain| (domain, "$LOCALDOMAIN"));
+    }
+
+    let contents = std::fs::read(RESOLV_CONF_FILE).ok()?;
+    let mut domain = None;
+
+    // The `domain` and `search` keywords override each other, so the last one wins.
+    for line in String::from_utf8_lossy(&contents).lines() {
+        let mut fields = line.split_ascii_whitespace();
+        if let Some("domain" | "search") = fields.next() {
+            domain = first_domain(fields);
+        }
+    }
+
+    domain.map(|domain| (domain, RESOLV_CONF_FILE))
+}
+
+/// Returns the first of `domains` without its trailing dot, unless that is the root domain.
+fn first_domain<'a>(mut domains: impl Iterator<Item = &'a str>) -> Option<String> {
+    let domain = domains.next()?.trim_end_matches('.');
+    (!domain.is_empty()).then(|| domain.to_owned())
+}
@@ -0,0 +1,123 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Turning arbitrary text, such as an asset name, into a valid host name.
+
+// spell-checker:ignore transliterate transliterated
+
+/// ASCII letters for U+00C0 to U+017F (Latin-1 Supplement and Latin Extended-A), with the
+/// diacritics removed. `?` marks the characters that are not letters, or that are transliterated
+/// to several letters.
+const LATIN_LETTERS: &[u8; 192] = b"\
+    aaaaaa?ceeeeiiiidnooooo?ouuuuy??aaaaaa?ceeeeiiiidnooooo?ouuuuy?y\
+    aaaaaaccccccccddddeeeeeeeeeegggggggghhhhiiiiiiiiii??jjkkklllllll\
+    lllnnnnnnnnnoooooo??rrrrrrssssssssttttttuuuuuuuuuuuuwwyyyzzzzzzs";
+
+const MAX_LABEL_LEN: usize = 63;
+
+/// Returns `name` turned into a valid host name of at most `max_len` bytes, along with the kinds
+/// of changes that were needed. The result is empty if nothing usable is left.
+///
+/// Letters are transliterated to ASCII and lowercased. Runs of other characters become a single
+/// hyphen, except dots, which separate labels. Labels are trimmed of hyphens and truncated to 63
+/// bytes, and empty ones are dropped. Whole labels are dropped from the end to fit in `max_len`.
+pub(crate) fn sanitize(name: &str, max_len: usize) -> (String, Vec<&'static str>) {
+    let mut changes = Vec::new();
+
+    let ascii = transliterate(name);
+    if ascii != name {
+        changes.push("transliterated");
+    }
+    if ascii.bytes().any(|b| b.is_ascii_uppercase()) {
+        changes.push("lowercased");
+    }
+
+    let mut labels = Vec::new();
+    let (mut replaced, mut trimmed, mut truncated) = (false, false, false);
+
+    for label in ascii.split('.') {
+        let cleaned = sanitize_label(label);
+        replaced |= cleaned.len() != label.len()
+            || cleaned
+                .bytes()
+                .zip(label.bytes())
+                .any(|(a, b)| a != b.to_ascii_lowercase());
+
+        let mut label = cleaned.trim_matches('-');
+        trimmed |= label.len() != cleaned.len();
+
+        if label.len() > MAX_LABEL_LEN {
+            label = label[..MAX_LABEL_LEN].trim_end_matches('-');
+            truncated = true;
+        }
+
+        if label.is_empty() {
+            trimmed = true;
+        } else {
+            labels.push(label.to_owned());
+        }
+    }
+
+    let mut host_name = labels.join(".");
+    while host_name.len() > max_len {
+        match host_name.rfind('.') {
+            Some(dot) => host_name.truncate(dot),
+            None => {
+                host_name.truncate(max_len);
+                host_name.truncate(host_name.trim_end_matches('-').len());
+            }
+        }
+        truncated = true;
+    }
+
+    if replaced {
+        changes.push("replaced invalid characters");
+    }
+    if trimmed {
+        changes.push("removed empty labels and leading or trailing hyphens");
+    }
+    if truncated {
+        changes.push("truncated");
+    }
+
+    (host_name, changes)
+}
+
+/// Lowercases `value` and replaces every run of characters other than ASCII letters and digits
+/// by a single hyphen, after transliterating letters to ASCII.
+pub(crate) fn sanitize_label(value: &str) -> String {
+    let mut label = String::with_capacity(value.len());
+    for c in transliterate(value).chars() {
+        if c.is_ascii_alphanumeric() {
+            label.push(c.to_ascii_lowercase());
+        } else if !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    label
+}
+
+/// Replaces the Latin letters with diacritics, and ligatures, by their closest ASCII spelling.
+/// Other characters are kept as they are.
+fn transliterate(value: &str) -> String {
+    let mut ascii = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            'Æ' | 'æ' => ascii.push_str("ae"),
+            'Þ' | 'þ' => ascii.push_str("th"),
+            'ß' => ascii.push_str("ss"),
+            'Ĳ' | 'ĳ' => ascii.push_str("ij"),
+            'Œ' | 'œ' => ascii.push_str("oe"),
+            '\u{c0}'..='\u{17f}' => match LATIN_LETTERS[c as usize - 0xc0] {
+                b'?' => ascii.push(c),
+                letter => ascii.push(char::from(letter)),
+            },
+            c => ascii.push(c),
+        }
+    }
+
+    ascii
+}
@@ -0,0 +1,136 @@
+// This file is part of the uutils hostname package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::io::{Error, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+use uucore::display::Quotable;
+use uucore::error::{FromIo, UResult};
+
+use crate::errors::HostNameError;
+use crate::utils::parse_host_name_file;
+
+pub(crate) const HOSTNAME_FILE: &str = "/etc/hostname";
+pub(crate) const HOSTS_FILE: &str = "/etc/hosts";
+pub(crate) const MACHINE_ID_FILE: &str = "/etc/machine-id";
+pub(crate) const MACHINE_INFO_FILE: &str = "/etc/machine-info";
+pub(crate) const DEFAULT_DOMAIN_FILE: &str = "/etc/defaultdomain";
+pub(crate) const RESOLV_CONF_FILE: &str = "/etc/resolv.conf";
+
+/// The root directory of the system whose configuration is read or written.
+///
+/// By default, this is the running system. With `--root`, this is an offline OS image mounted
+/// somewhere, and every system file is looked up below that directory instead.
+#[derive(Clone, Default)]
+pub(crate) struct SysRoot {
+    dir: Option<PathBuf>,
+}
+
+impl SysRoot {
+    pub(crate) fn image(dir: &Path) -> UResult<Self> {
+        let dir = dir
+            .canonicalize()
+            .map_err_context(|| format!("cannot access root directory {}", dir.quote()))?;
+
+        if dir.is_dir() {
+            Ok(Self { dir: Some(dir) })
+        } else {
+            Err(ErrorKind::NotADirectory
+                .map_err_context(|| format!("invalid root directory {}", dir.quote())))
+        }
+    }
+
+    /// Returns `true` if this designates an offline image rather than the running system.
+    pub(crate) fn is_image(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Reads the name stored alone in the system file `path`, such as `/etc/hostname`.
+    ///
+    /// Returns `None` if the file does not exist or does not hold a name.
+    pub(crate) fn read_name(&self, path: &str) -> UResult<Option<Vec<u8>>> {
+        let path = self.resolve(path)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let name = parse_host_name_file(&path)?;
+        let name = name.trim_ascii();
+        Ok((!name.is_empty()).then(|| name.to_vec()))
+    }
+
+    /// Maps the absolute `path` of a system file to its location below the root directory.
+    ///
+    /// Symbolic links are followed the way the image itself would see them: absolute targets
+    /// are relative to the root directory. A symbolic link that climbs above the root directory
+    /// is refused.
+    pub(crate) fn resolve(&self, path: &str) -> UResult<PathBuf> {
+        const MAX_SYMBOLIC_LINKS: usize = 40;
+
+        let Some(dir) = &self.dir else {
+            return Ok(PathBuf::from(path));
+        };
+
+        let mut pending: Vec<PathBuf> = components(Path::new(path));
+        let mut resolved = dir.clone();
+        let mut link_count = 0;
+        let mut last_link = None;
+
+        while let Some(component) = pending.pop() {
+            if component.as_os_str() == ".." {
+                if resolved == *dir {
+                    let link = last_link.unwrap_or_else(|| PathBuf::from(path));
+                    return Err(Box::new(HostNameError::EscapesRoot(link)));
+                }
+                resolved.pop();
+                continue;
+            }
+
+            resolved.push(&component);
+
+            let is_symlink = match resolved.symlink_metadata() {
+                Ok(metadata) => metadata.file_type().is_symlink(),
+                Err(err) if err.kind() == ErrorKind::NotFound => false,
+                Err(err) => {
+                    return Err(err)
+                        .map_err_context(|| format!("cannot access {}", resolved.quote()));
+                }
+            };
+
+            if is_symlink {
+                link_count += 1;
+                if link_count > MAX_SYMBOLIC_LINKS {
+                    return Err(Error::from_raw_os_error(libc::ELOOP))
+                        .map_err_context(|| format!("cannot access {}", resolved.quote()));
+                }
+
+                let target = resolved
+                    .read_link()
+                    .map_err_context(|| format!("cannot read link {}", resolved.quote()))?;
+
+                last_link = Some(resolved.clone());
+                resolved.pop();
+                if target.is_absolute() {
+                    resolved.clone_from(dir);
+                }
+                pending.extend(components(&target));
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// Returns the normal and parent components of `path`, in reverse order.
+fn components(path: &Path) -> Vec<PathBuf> {
+    path.components()
+        .rev()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(PathBuf::from(name)),
+            Component::ParentDir => Some(PathBuf::from("..")),
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => None,
+        })
+        .collect()
+}
@@ -1,11 +1,62 @@
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufRead, BufReader};
 use std::path::Path;
+#[cfg(not(target_family = "windows"))]
+use std::sync::atomic::{AtomicU8, Ordering};
 
 use uucore::error::UResult;
 
+/// How many times `--verbose` was given.
+#[cfg(not(target_family = "windows"))]
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+#[cfg(not(target_family = "windows"))]
+pub(crate) fn set_verbosity(level: u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
+
+/// Prints `message` on the standard error if `--verbose` was given at least `level` times.
+#[cfg(not(target_family = "windows"))]
+pub(crate) fn verbose(level: u8, message: std::fmt::Arguments) {
+    if VERBOSITY.load(Ordering::Relaxed) >= level {
+        eprintln!("{}: {message}", uucore::util_name());
+    }
+}
+
+/// Returns `value` as a JSON string literal, quotes included.
+#[cfg(not(target_family = "windows"))]
+pub(crate) fn json_string(value: &str) -> String {
+    use std::fmt::Write;
+
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(c));
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Reads the host name from `path`, or from the standard input if `path` is `-`.
 pub(crate) fn parse_host_name_file(path: &Path) -> UResult<Vec<u8>> {
-    let mut file = std::fs::File::open(path).map(BufReader::new)?;
+    if path == Path::new("-") {
+        parse_host_name(std::io::stdin().lock())
+    } else {
+        parse_host_name(std::fs::File::open(path).map(BufReader::new)?)
+    }
+}
 
+/// Returns the first line that is neither empty nor a comment.
+pub(crate) fn parse_host_name(mut file: impl BufRead) -> UResult<Vec<u8>> {
     let first_byte = loop {
         let mut first_byte = [0_u8; 1];
         if let Err(err) = file.read_exact(&mut first_byte)
@@ -33,3 +84,75 @@ pub(crate) fn parse_host_name_file(path: &Path) -> UResult<Vec<u8>> {
     }
     Ok(buffer)
 }
+
+/// Replaces the contents of `path` with `contents`, such that readers observe either the old
+/// or the new contents, but never a partially written file.
+///
+/// The permissions of an existing file are preserved. A symbolic link at `path` is followed, so
+/// that the file it points to is replaced rather than the link. A file that is a mount point,
+/// such as a bind-mounted `/etc/hosts` in a container, cannot be replaced, so it is overwritten
+/// in place instead.
+#[cfg(not(target_family = "windows"))]
+pub(crate) fn write_file_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::ffi::OsString;
+    use std::fs::{OpenOptions, Permissions};
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let path = &follow_symbolic_links(path)?;
+    let permissions = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.permissions(),
+        Err(err) if err.kind() == ErrorKind::NotFound => Permissions::from_mode(0o644),
+        Err(err) => return Err(err),
+    };
+
+    let mut temporary_name = OsString::from(".");
+    temporary_name.push(path.file_name().unwrap_or_default());
+    temporary_name.push(format!(".{}.tmp", std::process::id()));
+    let temporary_path = path.with_file_name(temporary_name);
+
+    let result = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.set_permissions(permissions)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temporary_path, path));
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = std::fs::remove_file(&temporary_path);
+            if err.kind() == ErrorKind::ResourceBusy {
+                std::fs::write(path, contents)
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Returns the path of the file that the symbolic links at `path` lead to, which need not
+/// exist.
+#[cfg(not(target_family = "windows"))]
+fn follow_symbolic_links(path: &Path) -> std::io::Result<std::path::PathBuf> {
+    const MAX_SYMBOLIC_LINKS: usize = 40;
+
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMBOLIC_LINKS {
+        match path.symlink_metadata() {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                // An absolute target replaces the parent directory.
+                let target = path.read_link()?;
+                path = path.with_file_name("").join(target);
+            }
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+            _ => return Ok(path),
+        }
+    }
+    Err(std::io::Error::from_raw_os_error(libc::ELOOP))
+}
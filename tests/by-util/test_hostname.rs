@@ -3,9 +3,1065 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use uutests::new_ucmd;
+#[cfg(target_os = "linux")]
+use uutests::util::CmdResult;
+use uutests::util::{TestScenario, UCommand};
+use uutests::{new_ucmd, util_name};
+
+/// Invokes the binary directly: through `new_ucmd!()`, the multi-call binary (which is itself
+/// named `hostname`) would receive the utility name as a positional host name argument.
+fn hostname_cmd() -> UCommand {
+    TestScenario::new(util_name!()).cmd(crate::TESTS_BINARY)
+}
+
+/// Runs `script` with sh(1) in the new namespaces given as options of unshare(1), as root of a
+/// new user namespace, with the binary in `$BIN`. Returns `None` where the namespaces cannot be
+/// created, or where ip(8), which scripts use to set up network namespaces, is missing.
+#[cfg(target_os = "linux")]
+fn run_in_namespaces(ts: &TestScenario, namespaces: &str, script: &str) -> Option<CmdResult> {
+    let succeeds = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    };
+    let available = succeeds("unshare", &["-r", namespaces, "true"]) && succeeds("ip", &["-V"]);
+    available.then(|| {
+        ts.cmd("unshare")
+            .args(&["-r", namespaces, "sh", "-c", script])
+            .env("BIN", crate::TESTS_BINARY)
+            .run()
+    })
+}
 
 #[test]
 fn test_invalid_arg() {
     new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_netns_not_found() {
+    hostname_cmd()
+        .args(&["--netns", "uu-hostname-does-not-exist", "-I"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot open network namespace");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_netns() {
+    let ts = TestScenario::new(util_name!());
+    let ready = ts.fixtures.plus("ready");
+    // A namespace whose only address besides the loopback one is on a bridge, kept alive by
+    // `sleep` for as long as the queries take.
+    let script = format!(
+        "unshare -n sh -c 'ip link add uu0 type bridge \
+         && ip address add 198.51.100.7/24 dev uu0 && ip link set uu0 up \
+         && touch {ready} && exec sleep 10' &
+         for _ in $(seq 100); do [ -e {ready} ] && break; sleep 0.05; done
+         \"$BIN\" --netns /proc/$!/ns/net -I
+         \"$BIN\" --netns /proc/$!/ns/net -A -I
+         kill $!",
+        ready = ready.display()
+    );
+    let Some(result) = run_in_namespaces(&ts, "-U", &script) else {
+        return;
+    };
+
+    result.stdout_is("198.51.100.7\nall-fqdns:\nall-ip-addresses: 198.51.100.7\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_exec_in_new_uts_namespace() {
+    let ts = TestScenario::new(util_name!());
+    let kernel_name = hostname_cmd().succeeds().stdout_move_str();
+
+    // The audit log is a directory, so recording both changes fails, which is reported once.
+    let script = "\"$BIN\" --exec uu-exec --nis-domain uu-nis --audit-log / -- \
+                  cat /proc/sys/kernel/hostname /proc/sys/kernel/domainname";
+    let Some(result) = run_in_namespaces(&ts, "-U", script) else {
+        return;
+    };
+    result.success().stdout_is("uu-exec\nuu-nis\n");
+    assert_eq!(
+        result
+            .stderr_str()
+            .matches("cannot write audit log")
+            .count(),
+        1
+    );
+
+    hostname_cmd().succeeds().stdout_is(kernel_name);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_root_set_and_query() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write(
+        "image/etc/hosts",
+        "127.0.0.1 localhost\n127.0.1.1 web1.example.com web1\n",
+    );
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "web1"])
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("image/etc/hostname"), "web1\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "-f"])
+        .succeeds()
+        .stdout_only("web1.example.com\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "-d"])
+        .succeeds()
+        .stdout_only("example.com\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_root_refuses_escaping_symlink() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("outside", "");
+    at.relative_symlink_file("../../outside", "image/etc/hostname");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "web1"])
+        .fails()
+        .stderr_contains("leads outside of the root directory");
+    assert_eq!(at.read("outside"), "");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_hostnamectl_pretty_name() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.symlink_file(crate::TESTS_BINARY, "hostnamectl");
+    let hostnamectl = at.plus("hostnamectl");
+
+    ts.cmd(&hostnamectl)
+        .args(&["--root", "image", "hostname", "Lennart's Laptop"])
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("image/etc/hostname"), "LennartsLaptop\n");
+    assert_eq!(
+        at.read("image/etc/machine-info"),
+        "PRETTY_HOSTNAME=\"Lennart's Laptop\"\n"
+    );
+
+    ts.cmd(&hostnamectl)
+        .args(&["--root", "image", "status"])
+        .succeeds()
+        .stdout_only("   Static hostname: LennartsLaptop\n   Pretty hostname: Lennart's Laptop\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_machine_info_edit_preserves_file() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write(
+        "image/etc/machine-info",
+        "# Inventory\nPRETTY_HOSTNAME='Web \"One\"'\nCUSTOM=kept\nCHASSIS=server\n",
+    );
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "CHASSIS=vm"])
+        .succeeds()
+        .no_output();
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "LOCATION=Rack 4"])
+        .succeeds()
+        .no_output();
+    assert_eq!(
+        at.read("image/etc/machine-info"),
+        "# Inventory\nPRETTY_HOSTNAME='Web \"One\"'\nCUSTOM=kept\nCHASSIS=vm\nLOCATION=\"Rack 4\"\n"
+    );
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "PRETTY_HOSTNAME"])
+        .succeeds()
+        .stdout_only("Web \"One\"\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "CHASSIS=spaceship"])
+        .fails()
+        .stderr_contains("invalid value for CHASSIS");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "DEPLOYMENT"])
+        .fails()
+        .code_is(1)
+        .stderr_only(format!(
+            "hostname: {}: DEPLOYMENT is not set\n",
+            at.plus_as_string("image/etc/machine-info")
+        ));
+
+    // A file that is not valid UTF-8 is read, but not rewritten.
+    let contents = b"PRETTY_HOSTNAME=Web\nCUSTOM=caf\xe9\n";
+    at.write_bytes("image/etc/machine-info", contents);
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "PRETTY_HOSTNAME"])
+        .succeeds()
+        .stdout_only("Web\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--machine-info", "CHASSIS=vm"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("machine-info:2: not valid UTF-8, refusing to rewrite the file");
+    assert_eq!(at.read_bytes("image/etc/machine-info"), contents);
+}
+
+/// Runs `hostname --via-hostnamed` against a private message bus, where `dbus-test-tool` stands
+/// in for systemd-hostnamed and accepts any call. Skipped when the D-Bus tools are missing.
+#[test]
+#[cfg(unix)]
+fn test_via_hostnamed_stand_in() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let Ok(mut bus) = Command::new("dbus-daemon")
+        .args(["--session", "--nofork", "--print-address"])
+        .stdout(Stdio::piped())
+        .spawn()
+    else {
+        return;
+    };
+    let mut address = String::new();
+    BufReader::new(bus.stdout.take().unwrap())
+        .read_line(&mut address)
+        .unwrap();
+    let address = address.trim_end();
+
+    let Ok(mut service) = Command::new("dbus-test-tool")
+        .args(["echo", "--name=org.freedesktop.hostname1"])
+        .env("DBUS_SESSION_BUS_ADDRESS", address)
+        .spawn()
+    else {
+        bus.kill().unwrap();
+        bus.wait().unwrap();
+        return;
+    };
+
+    let kernel_name = hostname_cmd().succeeds().stdout_move_str();
+
+    // The stand-in takes a moment to claim its name; until then, the bus reports it unknown.
+    let mut result = None;
+    for _ in 0..50 {
+        let attempt = hostname_cmd()
+            .env("DBUS_SYSTEM_BUS_ADDRESS", address)
+            .args(&["--via-hostnamed", "uu-stand-in"])
+            .run();
+        if attempt.succeeded() {
+            result = Some(attempt);
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+
+    for child in [&mut service, &mut bus] {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    result
+        .expect("hostnamed stand-in never answered")
+        .no_output();
+    hostname_cmd().succeeds().stdout_is(kernel_name);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_via_hostnamed_unavailable() {
+    let ts = TestScenario::new(util_name!());
+    let bus = ts.fixtures.plus("no-bus");
+    let address = format!("unix:path={}", bus.display());
+
+    ts.cmd(crate::TESTS_BINARY)
+        .env("DBUS_SYSTEM_BUS_ADDRESS", &address)
+        .arg("--via-hostnamed")
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot connect to the system bus");
+    ts.cmd(crate::TESTS_BINARY)
+        .env("DBUS_SYSTEM_BUS_ADDRESS", &address)
+        .arg("--via-hostnamed=auto")
+        .succeeds();
+}
+
+/// Without `--via-hostnamed`, the system bus is left alone, so that a bus that accepts but
+/// never answers cannot hold up a query.
+#[test]
+#[cfg(unix)]
+fn test_query_does_not_connect_to_bus() {
+    let ts = TestScenario::new(util_name!());
+    let bus = ts.fixtures.plus("silent-bus");
+    let listener = std::os::unix::net::UnixListener::bind(&bus).unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    for args in [&[][..], &["-s"]] {
+        ts.cmd(crate::TESTS_BINARY)
+            .env(
+                "DBUS_SYSTEM_BUS_ADDRESS",
+                format!("unix:path={}", bus.display()),
+            )
+            .args(args)
+            .succeeds();
+    }
+    assert!(listener.accept().is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_file_formats() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("network", "NETWORKING=yes\nHOSTNAME='web-01' # primary\n");
+    at.write("meta-data", "instance-id: i-1\nlocal-hostname: \"db-02\"\n");
+    at.write("rc.conf", "hostname=\"unterminated\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "-F",
+            "network",
+            "--file-format=shell-var:HOSTNAME",
+        ])
+        .succeeds();
+    assert_eq!(at.read("image/etc/hostname"), "web-01\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "-F", "meta-data", "--file-format=auto"])
+        .succeeds();
+    assert_eq!(at.read("image/etc/hostname"), "db-02\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "-F", "-"])
+        .pipe_in("# comment\n\nfrom-stdin\n")
+        .succeeds();
+    assert_eq!(at.read("image/etc/hostname"), "from-stdin\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "-F",
+            "rc.conf",
+            "--file-format=shell-var:hostname",
+        ])
+        .fails()
+        .stderr_contains("rc.conf:1: unterminated quoted string");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "-F",
+            "network",
+            "--file-format=yaml-key:hostname",
+        ])
+        .fails()
+        .stderr_contains("network: hostname is not set");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_from_cmdline() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write(
+        "cmdline",
+        "BOOT_IMAGE=/vmlinuz \"hostname=not this\" hostname=plain systemd.hostname=\"quoted\" \
+         -- systemd.hostname=for-init\n",
+    );
+    at.write("cmdline-without", "ro quiet\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--from-cmdline",
+            "--cmdline-file",
+            "cmdline",
+        ])
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("image/etc/hostname"), "quoted\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--from-cmdline",
+            "--cmdline-file",
+            "cmdline-without",
+        ])
+        .fails()
+        .stderr_contains("no host name on the kernel command line");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--from-cmdline",
+            "--cmdline-file",
+            "cmdline-without",
+        ])
+        .args(&["-b", "-F", "missing"])
+        .succeeds();
+    assert_eq!(at.read("image/etc/hostname"), "localhost\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_from_dhcp_lease_file() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.write(
+        "dhclient.leases",
+        "lease {\n  interface \"eth0\";\n  option host-name \"expired\";\n  \
+         expire 1 2001/01/01 00:00:00;\n}\n\
+         lease {\n  interface \"eth0\";\n  option host-name \"current\";\n  \
+         expire 4 2099/10/15 12:00:00;\n}\n\
+         lease {\n  interface \"eth0\";\n  option host-name \"renewed\";\n  \
+         expire 1 2099/01/05 12:00:00;\n}\n",
+    );
+    at.write("networkd-lease", "ADDRESS=192.0.2.7\nLIFETIME=3600\n");
+
+    // dhclient appends renewals, so the last current lease wins even if it expires sooner.
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--from-dhcp=./dhclient.leases", "--dry-run"])
+        .succeeds()
+        .stdout_contains(" -> 'renewed'\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--from-dhcp=./networkd-lease", "--dry-run"])
+        .fails()
+        .stderr_contains("no current DHCP lease with a host name");
+
+    // A lease file that cannot be read is skipped.
+    at.mkdir_all("image/etc");
+    at.mkdir_all("image/var/lib/dhcp/dhclient-eth1.leases");
+    at.copy("dhclient.leases", "image/var/lib/dhcp/dhclient-eth0.leases");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--from-dhcp", "--dry-run"])
+        .succeeds()
+        .stdout_contains(" -> 'renewed'\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_generate() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("image/etc/machine-id", "0A1B2C3D4E5F60718293a4b5c6d7e8f9\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--generate", "node-{machine-id:8}"])
+        .succeeds()
+        .stdout_only("node-0a1b2c3d\n");
+    assert!(!at.file_exists("image/etc/hostname"));
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--generate",
+            "box-{random:alnum:6}",
+            "--apply",
+        ])
+        .succeeds()
+        .no_output();
+    let host_name = at.read("image/etc/hostname");
+    assert!(host_name.starts_with("box-") && host_name.len() == "box-123456\n".len());
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--generate", "web-{nothing}"])
+        .fails()
+        .stderr_contains("invalid template: unknown placeholder {nothing}");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_sanitize() {
+    hostname_cmd()
+        .args(&["--sanitize", "--dry-run", " Büro_Straße  #3.Example..COM. "])
+        .succeeds()
+        .stdout_contains(" -> 'buro-strasse-3.example.com'\n")
+        .no_stderr();
+
+    hostname_cmd()
+        .args(&["--sanitize", "--dry-run", "--verbose", "Web_01"])
+        .succeeds()
+        .stdout_contains(" -> 'web-01'\n")
+        .stderr_contains(
+            "hostname: sanitized 'Web_01' to 'web-01' (lowercased, replaced invalid characters)\n",
+        );
+
+    hostname_cmd()
+        .args(&["--sanitize", "--dry-run", "--verbose", "db-02"])
+        .succeeds()
+        .stdout_contains(" -> 'db-02'\n")
+        .stderr_does_not_contain("sanitized");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_set_fqdn() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write(
+        "image/etc/hosts",
+        "127.0.0.1\tlocalhost\n127.0.1.1\told.example.com old\n::1\tlocalhost\n",
+    );
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--set-fqdn", "web1.prod.example.com"])
+        .succeeds()
+        .no_output();
+    assert_eq!(at.read("image/etc/hostname"), "web1\n");
+    assert_eq!(
+        at.read("image/etc/hosts"),
+        "127.0.0.1\tlocalhost\n127.0.1.1\tweb1.prod.example.com web1\n::1\tlocalhost\n"
+    );
+
+    for (flag, expected) in [
+        ("-s", "web1\n"),
+        ("-f", "web1.prod.example.com\n"),
+        ("-d", "prod.example.com\n"),
+    ] {
+        ts.cmd(crate::TESTS_BINARY)
+            .args(&["--root", "image", flag])
+            .succeeds()
+            .stdout_only(expected);
+    }
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--set-fqdn", "db.example.org"])
+        .args(&["--fqdn-policy", "hostname-file"])
+        .succeeds();
+    assert_eq!(at.read("image/etc/hostname"), "db.example.org\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--set-fqdn", "single"])
+        .fails()
+        .stderr_contains("not fully qualified");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_failing_pre_change_hook_cancels_change() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("hooks/pre-change");
+    at.write(
+        "hooks/pre-change/10-record",
+        "#!/bin/sh\necho \"$OLD_HOSTNAME -> $NEW_HOSTNAME\" > \"$0.out\"\n",
+    );
+    at.write("hooks/pre-change/20-refuse", "#!/bin/sh\nexit 3\n");
+    at.write(
+        "hooks/pre-change/30-never-run",
+        "#!/bin/sh\ntouch \"$0.out\"\n",
+    );
+    at.write(
+        "hooks/pre-change/ignored.sh",
+        "#!/bin/sh\ntouch \"$0.out\"\n",
+    );
+    for hook in ["10-record", "20-refuse", "30-never-run", "ignored.sh"] {
+        at.set_mode(&format!("hooks/pre-change/{hook}"), 0o755);
+    }
+
+    let kernel_name = hostname_cmd().succeeds().stdout_move_str();
+
+    // The change is attempted in a new UTS namespace, so that the name of the machine stays
+    // the same whether or not the hook cancels it.
+    let script = "\"$BIN\" uu-hook-old && { \"$BIN\" --hooks-dir hooks uu-hook-test; \
+                  echo \"exit $?\"; \"$BIN\"; }";
+    let Some(result) = run_in_namespaces(&ts, "-Uu", script) else {
+        return;
+    };
+    result
+        .success()
+        .stdout_is("exit 1\nuu-hook-old\n")
+        .stderr_contains("20-refuse' failed: exit status: 3");
+
+    assert_eq!(
+        at.read("hooks/pre-change/10-record.out"),
+        "uu-hook-old -> uu-hook-test\n"
+    );
+    assert!(!at.file_exists("hooks/pre-change/30-never-run.out"));
+    assert!(!at.file_exists("hooks/pre-change/ignored.sh.out"));
+    hostname_cmd().succeeds().stdout_is(kernel_name);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_set_fqdn_through_links_and_mounts() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    let kernel_name = hostname_cmd().succeeds().stdout_move_str();
+    at.mkdir("etc");
+    at.write("etc/hosts.real", "127.0.0.1\tlocalhost\n");
+    std::os::unix::fs::symlink("hosts.real", at.plus("etc/hosts")).unwrap();
+    at.write("read-only-hosts", "127.0.0.1\tlocalhost\n");
+    at.write("mounted-hosts", "127.0.0.1\tlocalhost\n");
+
+    // A read-only /etc/hosts fails the change before the name is set. A bind-mounted one is
+    // written in place, and an /etc/hosts that is a symbolic link is written through.
+    let script = "\"$BIN\" uu-old && mount --bind read-only-hosts /etc/hosts \
+                  && mount -o remount,ro,bind /etc/hosts \
+                  && { \"$BIN\" --set-fqdn uu-ro.example.org; echo \"exit $?\"; \"$BIN\"; } \
+                  && umount /etc/hosts && mount --bind mounted-hosts /etc/hosts \
+                  && \"$BIN\" --set-fqdn uu-mount.example.org && \"$BIN\" \
+                  && umount /etc/hosts && mount --bind etc /etc \
+                  && \"$BIN\" --set-fqdn uu-link.example.org && \"$BIN\"";
+    let Some(result) = run_in_namespaces(&ts, "-Uum", script) else {
+        return;
+    };
+    result
+        .success()
+        .stdout_is("exit 1\nuu-old\nuu-mount\nuu-link\n")
+        .stderr_is("hostname: cannot write '/etc/hosts': Read-only file system\n");
+
+    assert_eq!(at.read("read-only-hosts"), "127.0.0.1\tlocalhost\n");
+    assert_eq!(
+        at.read("mounted-hosts"),
+        "127.0.0.1\tlocalhost\n127.0.1.1\tuu-mount.example.org uu-mount\n"
+    );
+    assert!(at.is_symlink("etc/hosts"));
+    assert_eq!(
+        at.read("etc/hosts.real"),
+        "127.0.0.1\tlocalhost\n127.0.1.1\tuu-link.example.org uu-link\n"
+    );
+    hostname_cmd().succeeds().stdout_is(kernel_name);
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_audit_log() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("image/etc/hostname", "old\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--audit-log", "audit.jsonl", "new"])
+        .succeeds()
+        .no_output();
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--audit-log", "audit.jsonl"])
+        .args(&["--generate", "web-{random:4}", "--apply"])
+        .succeeds();
+
+    let log = at.read("audit.jsonl");
+    let lines: Vec<_> = log.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("{\"timestamp\":\""));
+    assert!(lines[0].contains("\"change\":\"hostname\""));
+    assert!(lines[0].contains("\"old\":\"old\",\"new\":\"new\",\"source\":\"argument\""));
+    assert!(lines[0].ends_with("\"result\":\"success\"}"));
+    assert!(lines[1].contains("\"old\":\"new\",\"new\":\"web-"));
+    assert!(lines[1].contains("\"source\":\"generated\""));
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_dry_run_plan() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("image/etc/hostname", "old\n");
+    at.write(
+        "image/etc/hosts",
+        "127.0.0.1\tlocalhost\n127.0.1.1\told.example.com old\n",
+    );
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--dry-run",
+            "--set-fqdn",
+            "web.example.com",
+        ])
+        .succeeds()
+        .stdout_contains("static host name: 'old' -> 'web'\n")
+        .stdout_contains("@@ -1,1 +1,1 @@\n-old\n+web\n")
+        .stdout_contains(
+            "@@ -1,2 +1,2 @@\n 127.0.0.1\tlocalhost\n\
+             -127.0.1.1\told.example.com old\n\
+             +127.0.1.1\tweb.example.com web\n",
+        );
+    assert_eq!(at.read("image/etc/hostname"), "old\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&[
+            "--root",
+            "image",
+            "--dry-run",
+            "--set-fqdn",
+            "old.example.com",
+        ])
+        .fails()
+        .code_is(2)
+        .stdout_only("static host name: 'old' (unchanged)\n");
+
+    for policy in ["hostname-file", "both"] {
+        at.write("image/etc/hostname", "old.example.org\n");
+        at.write(
+            "image/etc/hosts",
+            "127.0.0.1\tlocalhost\n127.0.1.1\told.example.org old\n",
+        );
+        ts.cmd(crate::TESTS_BINARY)
+            .args(&["--root", "image", "--dry-run", "--set-fqdn"])
+            .args(&["old.example.org", "--fqdn-policy", policy])
+            .fails()
+            .code_is(2)
+            .stdout_only("static host name: 'old.example.org' (unchanged)\n");
+
+        ts.cmd(crate::TESTS_BINARY)
+            .args(&["--root", "image", "--dry-run", "--set-fqdn"])
+            .args(&["new.example.org", "--fqdn-policy", policy])
+            .succeeds()
+            .stdout_contains("static host name: 'old.example.org' -> 'new.example.org'\n")
+            .stdout_contains("-old.example.org\n+new.example.org\n");
+    }
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_doctor() {
+    let kernel_name = hostname_cmd().succeeds().stdout_move_str();
+    let kernel_name = kernel_name.trim_end();
+
+    // Resolution depends on the machine, so only findings that do not are checked.
+    hostname_cmd()
+        .arg("--doctor")
+        .env("HOSTNAME", "uu-hostname-stale")
+        .run()
+        .stdout_contains(format!("kernel host name:        {kernel_name}\n"))
+        .stdout_contains(format!(
+            "info: $HOSTNAME ('uu-hostname-stale') differs from the kernel name '{kernel_name}'\n  \
+             fix: start a new login shell to pick up the current name\n"
+        ));
+
+    hostname_cmd()
+        .arg("--doctor=json")
+        .env("HOSTNAME", "uu-hostname-stale")
+        .run()
+        .stdout_contains(format!("{{\"kernel_host_name\":\"{kernel_name}\","))
+        .stdout_contains("\"environment_host_name\":\"uu-hostname-stale\"")
+        .stdout_contains("{\"severity\":\"info\",\"message\":\"$HOSTNAME (");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_check_fcrdns() {
+    // Whether the addresses pass depends on the DNS of the machine, but every one that -I prints
+    // gets a row.
+    let addresses = hostname_cmd().arg("-I").succeeds().stdout_move_str();
+    let result = hostname_cmd().arg("--check-fcrdns").run();
+    let table = result.stdout_str();
+
+    assert!(table.starts_with("ADDRESS "));
+    for address in addresses.split_whitespace() {
+        assert!(table.contains(&format!("\n{address} ")));
+    }
+    assert_eq!(result.succeeded(), !table.contains(" fail ("));
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_lookup() {
+    let result = hostname_cmd().args(&["--lookup", "127.0.0.1"]).succeeds();
+    assert!(
+        result
+            .stdout_str()
+            .starts_with("canonical name: 127.0.0.1\naddress: 127.0.0.1  ")
+    );
+
+    hostname_cmd()
+        .args(&["--lookup", "uu-hostname.invalid"])
+        .fails()
+        .no_stdout();
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_fallback_to_local_domain() {
+    let fqdn = hostname_cmd().arg("-f").run().stdout_move_str();
+    if fqdn.contains('.') {
+        return; // The resolver already gives a domain, which takes precedence.
+    }
+    let short_name = hostname_cmd().arg("-s").succeeds().stdout_move_str();
+
+    hostname_cmd()
+        .args(&["--fallback", "-f", "-v"])
+        .env("LOCALDOMAIN", "corp.example. other.example")
+        .succeeds()
+        .stdout_is(format!("{}.corp.example\n", short_name.trim_end()))
+        .stderr_contains("appending the local domain from $LOCALDOMAIN");
+    hostname_cmd()
+        .args(&["--fallback", "-d"])
+        .env("LOCALDOMAIN", "corp.example")
+        .succeeds()
+        .stdout_only("corp.example\n");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_several_queries() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write("image/etc/hostname", "web1\n");
+    at.write("image/etc/hosts", "10.0.0.5\tweb1.example.com web1\n");
+
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "-f", "-s", "-d", "-i"])
+        .succeeds()
+        .stdout_only(
+            "fqdn: web1.example.com\nshort: web1\ndomain: example.com\nip-address: 10.0.0.5\n",
+        );
+
+    // A missing answer leaves an empty line, so that the others keep their position.
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--no-labels", "-y", "-d", "-s"])
+        .fails()
+        .stdout_is("\nexample.com\nweb1\n")
+        .stderr_is("hostname: local domain name not set\n");
+
+    let short_name = hostname_cmd().arg("-s").succeeds().stdout_move_str();
+    hostname_cmd()
+        .args(&["-s", "-f"])
+        .run()
+        .stdout_contains(format!("short: {short_name}fqdn: "));
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_all_fqdns_and_all_ip_addresses() {
+    let all_fqdns = hostname_cmd().arg("-A").run().stdout_move_str();
+    let all_ip_addresses = hostname_cmd().arg("-I").succeeds().stdout_move_str();
+
+    hostname_cmd().args(&["-A", "-I"]).run().stdout_is(format!(
+        "all-fqdns:{}{all_fqdns}all-ip-addresses: {all_ip_addresses}",
+        if all_fqdns.trim().is_empty() { "" } else { " " }
+    ));
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_format() {
+    let name = hostname_cmd().succeeds().stdout_move_str();
+    let short_name = hostname_cmd().arg("-s").succeeds().stdout_move_str();
+    hostname_cmd()
+        .args(&["--format", r"%h\t%s 100%%\0"])
+        .succeeds()
+        .stdout_only(format!(
+            "{}\t{} 100%\0",
+            name.trim_end(),
+            short_name.trim_end()
+        ));
+
+    hostname_cmd()
+        .args(&["--format", "%h %q"])
+        .fails()
+        .no_stdout()
+        .stderr_is("hostname: invalid format: unknown placeholder %q\n");
+}
+
+/// A placeholder that fails is reported and left empty, and the others are still printed.
+#[test]
+#[cfg(target_os = "linux")]
+fn test_format_failing_placeholder() {
+    let ts = TestScenario::new(util_name!());
+    let script = "echo '(none)' > /proc/sys/kernel/domainname && \"$BIN\" uu-format \
+                  && \"$BIN\" --format '%h [%y] %s\\n'";
+    let Some(result) = run_in_namespaces(&ts, "-Uu", script) else {
+        return;
+    };
+    result
+        .failure()
+        .code_is(1)
+        .stdout_is("uu-format [] uu-format\n")
+        .stderr_is("hostname: local domain name not set\n");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_export() {
+    let name = hostname_cmd().succeeds().stdout_move_str();
+    hostname_cmd()
+        .arg("--export")
+        .run()
+        .stdout_contains(format!("export HOSTNAME='{}'\n", name.trim_end()))
+        .stdout_contains("export IP_ADDRESSES='");
+
+    let fqdn = hostname_cmd().arg("-f").run().stdout_move_str();
+    if fqdn.contains('.') {
+        return; // The resolver gives the domain, so $LOCALDOMAIN cannot inject one.
+    }
+    let domain = r"it's\$(x)";
+    hostname_cmd()
+        .args(&["--fallback", "--export"])
+        .env("LOCALDOMAIN", domain)
+        .run()
+        .stdout_contains(r"export DNSDOMAIN='it'\''s\$(x)'");
+    hostname_cmd()
+        .args(&["--fallback", "--export=fish"])
+        .env("LOCALDOMAIN", domain)
+        .run()
+        .stdout_contains(r"set -gx DNSDOMAIN 'it\'s\\$(x)'");
+    hostname_cmd()
+        .args(&["--fallback", "--export=pwsh"])
+        .env("LOCALDOMAIN", "it\u{2019}s")
+        .run()
+        .stdout_contains("$env:DNSDOMAIN = 'it\u{2019}\u{2019}s'");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_escape() {
+    let ts = TestScenario::new(util_name!());
+    let at = &ts.fixtures;
+    at.mkdir_all("image/etc");
+    at.write_bytes("image/etc/hostname", b"web\x1b[31m\\1\xff\n");
+
+    // Standard output is not a terminal here, so auto prints names as they are.
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image"])
+        .succeeds()
+        .stdout_only_bytes(b"web\x1b[31m\\1\xff\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--escape=c"])
+        .succeeds()
+        .stdout_only("web\\x1b[31m\\\\1\\xff\n");
+    ts.cmd(crate::TESTS_BINARY)
+        .args(&["--root", "image", "--escape=shell", "-s", "-y"])
+        .run()
+        .stdout_is("short: $'web\\x1b[31m\\\\1\\xff'\nnis:\n");
+}
+
+/// A PTR record or canonical name holding a control sequence is escaped in the reports too.
+#[test]
+#[cfg(target_os = "linux")]
+fn test_escape_resolver_names() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures
+        .write_bytes("hosts", b"10.9.9.9\tev\x1b[31mil.example evil\n");
+    let script = format!(
+        "mount --bind {} /etc/hosts && ip link add uu0 type bridge \
+         && ip address add 10.9.9.9/24 dev uu0 && ip link set uu0 up && \"$BIN\" evil \
+         && \"$BIN\" --escape=c --lookup evil && \"$BIN\" --escape=c --check-fcrdns \
+         && \"$BIN\" --escape=c --doctor",
+        ts.fixtures.plus("hosts").display()
+    );
+    let Some(result) = run_in_namespaces(&ts, "-mnu", &script) else {
+        return;
+    };
+
+    result
+        .stdout_contains("canonical name: ev\\x1b[31mil.example\n")
+        .stdout_contains("address: 10.9.9.9  ev\\x1b[31mil.example\n")
+        .stdout_contains("10.9.9.9  ev\\x1b[31mil.example  pass\n")
+        .stdout_contains("reverse DNS name:        ev\\x1b[31mil.example\n")
+        .stdout_does_not_contain("\x1b");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_prometheus() {
+    let name = hostname_cmd().succeeds().stdout_move_str();
+    hostname_cmd()
+        .arg("--prometheus")
+        .succeeds()
+        .stdout_contains("# TYPE hostname_info gauge\n")
+        .stdout_contains(format!("hostname_info{{hostname=\"{}\",", name.trim_end()))
+        .stdout_contains("hostname_lookup_success{lookup=\"hostname\"} 1\n")
+        .stdout_contains("hostname_lookup_duration_seconds{lookup=\"fqdn\"} ");
+}
+
+/// The interface address metrics count the addresses that `-I` prints, and no others.
+#[test]
+#[cfg(target_os = "linux")]
+fn test_prometheus_interface_addresses() {
+    let ts = TestScenario::new(util_name!());
+    let script = "ip link set lo up && ip link add uu0 type bridge \
+                  && ip address add 198.51.100.7/24 dev uu0 \
+                  && ip address add 2001:db8::7/64 dev uu0 nodad && ip link set uu0 up \
+                  && \"$BIN\" -I && \"$BIN\" --prometheus | grep ^hostname_interface_addresses";
+    let Some(result) = run_in_namespaces(&ts, "-n", script) else {
+        return;
+    };
+    result.success().stdout_is(
+        "198.51.100.7 2001:db8::7\n\
+         hostname_interface_addresses{interface=\"uu0\",family=\"ipv4\"} 1\n\
+         hostname_interface_addresses{interface=\"uu0\",family=\"ipv6\"} 1\n",
+    );
+}
+
+/// The description of the machine from /etc/machine-info is part of the structured output.
+#[test]
+#[cfg(target_os = "linux")]
+fn test_machine_info_in_structured_output() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.mkdir("etc");
+    ts.fixtures.write(
+        "etc/machine-info",
+        "CHASSIS=server\nLOCATION=\"Rack 7\"\nHARDWARE_VENDOR=Acme\n",
+    );
+    let script = format!(
+        "mount -t overlay overlay -o lowerdir={}:/etc /etc \
+         && {{ \"$BIN\" --doctor=json; \"$BIN\" --export; \"$BIN\" --prometheus; }}",
+        ts.fixtures.plus("etc").display()
+    );
+    let Some(result) = run_in_namespaces(&ts, "-m", &script) else {
+        return;
+    };
+
+    result
+        .stdout_contains(
+            "\"machine_info\":{\"CHASSIS\":\"server\",\"DEPLOYMENT\":null,\
+             \"LOCATION\":\"Rack 7\",\"ICON_NAME\":null,\"HARDWARE_VENDOR\":\"Acme\",\
+             \"HARDWARE_MODEL\":null}",
+        )
+        .stdout_contains("export CHASSIS='server'\nexport DEPLOYMENT=''\n")
+        .stdout_contains(",chassis=\"server\",deployment=\"\",location=\"Rack 7\",");
+}
+
+#[test]
+#[cfg(not(target_family = "windows"))]
+fn test_verbose_trace() {
+    let name = hostname_cmd().succeeds().stdout_move_str();
+    hostname_cmd()
+        .args(&["-v", "-f"])
+        .run()
+        .stderr_contains(format!("gethostname: '{}' in ", name.trim_end()))
+        .stderr_contains(format!("getaddrinfo('{}', hints: ", name.trim_end()))
+        .stderr_does_not_contain("buffer of");
+
+    hostname_cmd()
+        .args(&["-v", "-I"])
+        .succeeds()
+        .stderr_contains("getnameinfo(")
+        .stderr_does_not_contain("address family");
+
+    // -vv adds the buffer sizes, socket types and protocols.
+    hostname_cmd()
+        .args(&["-vv", "-f"])
+        .run()
+        .stderr_contains("  buffer of ");
+}